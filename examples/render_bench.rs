@@ -0,0 +1,84 @@
+/*
+Times drawing the Events and Calendar pages against data_bases of growing size.
+
+Drawing only reads the cached page state, so the Events view time stays roughly flat as the number of
+occurrences grows, while refresh times (the data_base queries) grow with it. The Calendar view
+grows only with the number of occurrences in the displayed month, since it prints each one.
+
+Run with `cargo run --release --example render_bench`.
+ */
+use chrono::{Datelike, Duration, Local};
+use rusqlite::params;
+use since_when_lib::calendar::Calendar;
+use since_when_lib::database::{Result, Store};
+use since_when_lib::events::EventsPage;
+use since_when_lib::tags::TagFilter;
+use since_when_lib::utils;
+use std::time::Instant;
+
+const EVENTS: usize = 10_000;
+const VIEWS: u32 = 20;
+
+/// Build an in-memory data_base with `EVENTS` events of `occurrences` each.
+fn populate(occurrences: usize) -> Result<Store> {
+    let store = Store::open_in_memory()?;
+    {
+        let conn = store.connection();
+        let tx = conn.unchecked_transaction()?;
+        let mut event = tx.prepare("INSERT INTO events (name) VALUES (?1);")?;
+        let mut occurrence =
+            tx.prepare("INSERT INTO occurrences (event_id, date) VALUES (?1, ?2);")?;
+        let today = Local::now().date_naive();
+        for e in 0..EVENTS {
+            event.execute(params![format!("Event {}", e)])?;
+            let id = tx.last_insert_rowid();
+            for o in 0..occurrences {
+                let date = today - Duration::days((o * 7 + e % 7) as i64);
+                occurrence.execute(params![id, date])?;
+            }
+        }
+        drop((event, occurrence));
+        tx.commit()?;
+    }
+    Ok(store)
+}
+
+fn main() -> Result<()> {
+    for occurrences in [1, 10, 100] {
+        let store = populate(occurrences)?;
+        let today = Local::now().date_naive();
+        let mut events = EventsPage::new();
+        let mut calendar = Calendar::new();
+        let filter = TagFilter::new();
+
+        let start = Instant::now();
+        let year_month = (today.year(), today.month());
+        events.loaded(utils::event_details(&store))?;
+        calendar.loaded(
+            year_month.0,
+            year_month.1,
+            store.events_by_year_month(year_month.0, year_month.1),
+        )?;
+        let refresh = start.elapsed();
+        // The views must not need the data_base at all.
+        drop(store);
+
+        let start = Instant::now();
+        for _ in 0..VIEWS {
+            let _ = events.view(&filter);
+        }
+        let events_view = start.elapsed() / VIEWS;
+
+        let start = Instant::now();
+        for _ in 0..VIEWS {
+            let _ = calendar.view(&filter, events.details());
+        }
+        let calendar_view = start.elapsed() / VIEWS;
+
+        println!(
+            "{} events x {} occurrences: refresh {:?}, events view {:?}, calendar view {:?}",
+            EVENTS, occurrences, refresh, events_view, calendar_view
+        );
+    }
+    Ok(())
+}
@@ -5,15 +5,26 @@ use log::info;
 
 use crate::{
     app::AppMessage,
-    database::{add_event, delete_event, update_event},
+    database::{Result, Store},
+    events::OccurrenceDetails,
     settings::Settings,
-    utils::{get_date, new_button},
+    undo::Change,
+    utils::{get_date, new_button, parse_amount, parse_tags, record},
 };
 
 /// AddEvent state.
+///
+/// Holds the event title, the details to record with the occurrence and the tags of a new
+/// event, as entered.
 #[derive(Debug, Clone)]
 pub struct AddEvent {
     event: String,
+    note: String,
+    quantity: String,
+    unit: String,
+    cost: String,
+    meter: String,
+    tags: String,
 }
 
 /// Default AddEvent implementation.
@@ -28,52 +39,107 @@ impl<'a> AddEvent {
     pub fn new() -> AddEvent {
         Self {
             event: String::new(),
+            note: String::new(),
+            quantity: String::new(),
+            unit: String::new(),
+            cost: String::new(),
+            meter: String::new(),
+            tags: String::new(),
         }
     }
 
+    /// The details entered for the occurrence.
+    ///
+    /// ### Returns
+    /// - `Result<OccurrenceDetails>` - `Error::InvalidInput` if the quantity, cost or meter
+    ///   reading is not a number.
+    fn details(&self) -> Result<OccurrenceDetails> {
+        let text = |input: &str| Some(input.trim().to_string()).filter(|text| !text.is_empty());
+        Ok(OccurrenceDetails {
+            note: text(&self.note),
+            quantity: parse_amount("quantity", &self.quantity)?,
+            unit: text(&self.unit),
+            cost: parse_amount("cost", &self.cost)?,
+            meter: parse_amount("meter reading", &self.meter)?,
+        })
+    }
+
     /// Add, Update or Delete Events.
     ///
+    /// Changes to the data_base run in the background.
+    ///
     /// ### Arguments
     /// - message: `AppMessage` - The message to process.
     /// - day: `u32` - The day of the date to add.
     /// - month: `u32` - The month of the date to add.
     /// - year: `i32` - The year of the date to add.
+    /// - store: `&Store` - The events data_base.
     ///
     /// ### Returns
-    /// - `Command<AppMessage>` - The command to execute.
+    /// - `Command<AppMessage>` - Sends `AppMessage::Changed` when done.
     pub fn update(
         &mut self,
         message: AppMessage,
         day: u32,
         month: u32,
         year: i32,
+        store: &Store,
     ) -> Command<AppMessage> {
+        let store = store.clone();
+        let event = self.event.clone();
         match message {
-            AppMessage::AddEvent => {
-                if self.event.is_empty() {
-                    return Command::none();
-                }
-                add_event(&self.event, year, month, day);
+            AppMessage::AddEvent if !event.is_empty() => {
+                let date = get_date(year, month, day);
+                let details = self.details();
+                let tags = parse_tags(&self.tags);
+                record(move || {
+                    let id = store.add_tagged_event(&event, date, &details?, &tags)?;
+                    Ok(Change::AddedEvent(id))
+                })
             }
-            AppMessage::UpdateEvent => {
-                if self.event.is_empty() {
-                    return Command::none();
-                }
-                update_event(&self.event, year, month, day);
-            }
-            AppMessage::DeleteEvent => {
-                if self.event.is_empty() {
-                    return Command::none();
-                }
-                delete_event(&self.event);
+            AppMessage::UpdateEvent if !event.is_empty() => {
+                let date = get_date(year, month, day);
+                let details = self.details();
+                record(move || {
+                    let id = store.update_event_with(&event, date, &details?)?;
+                    Ok(Change::AddedOccurrence(id))
+                })
             }
+            AppMessage::DeleteEvent if !event.is_empty() => record(move || {
+                let id = store.delete_event(&event)?;
+                Ok(Change::TrashedEvent(id, event))
+            }),
             AppMessage::TextEvent(s) => {
                 self.event = s;
                 info!("TextEvent: {:?}", self.event);
+                Command::none()
+            }
+            AppMessage::NoteText(s) => {
+                self.note = s;
+                Command::none()
+            }
+            AppMessage::QuantityText(s) => {
+                self.quantity = s;
+                Command::none()
+            }
+            AppMessage::UnitText(s) => {
+                self.unit = s;
+                Command::none()
             }
-            _ => (),
+            AppMessage::CostText(s) => {
+                self.cost = s;
+                Command::none()
+            }
+            AppMessage::MeterText(s) => {
+                self.meter = s;
+                Command::none()
+            }
+            AppMessage::TagsText(s) => {
+                self.tags = s;
+                Command::none()
+            }
+            _ => Command::none(),
         }
-        Command::none()
     }
 
     /// View for AddEvent.
@@ -97,6 +163,33 @@ impl<'a> AddEvent {
             .on_input(AppMessage::TextEvent)
             .size(settings.text_size())
             .width(500);
+        let note_input = text_input("Note (optional)", &self.note)
+            .on_input(AppMessage::NoteText)
+            .size(settings.text_size())
+            .width(500);
+        let amount_row = row![
+            text_input("Quantity", &self.quantity)
+                .on_input(AppMessage::QuantityText)
+                .size(settings.text_size())
+                .width(110),
+            text_input("Unit", &self.unit)
+                .on_input(AppMessage::UnitText)
+                .size(settings.text_size())
+                .width(90),
+            text_input("Cost", &self.cost)
+                .on_input(AppMessage::CostText)
+                .size(settings.text_size())
+                .width(110),
+            text_input("Meter", &self.meter)
+                .on_input(AppMessage::MeterText)
+                .size(settings.text_size())
+                .width(130),
+        ]
+        .spacing(settings.spacing());
+        let tags_input = text_input("Tags for a new event (comma separated)", &self.tags)
+            .on_input(AppMessage::TagsText)
+            .size(settings.text_size())
+            .width(500);
         // Action buttons.
         let add_button = new_button(
             AppMessage::AddEvent,
@@ -130,9 +223,10 @@ impl<'a> AddEvent {
         let nav_row = row![calendar_button, event_button]
             .align_items(Alignment::Center)
             .spacing(settings.spacing());
-        let content = column![date_text, input, action_row, nav_row]
-            .align_items(Alignment::Center)
-            .spacing(settings.spacing());
+        let content =
+            column![date_text, input, note_input, amount_row, tags_input, action_row, nav_row]
+                .align_items(Alignment::Center)
+                .spacing(settings.spacing());
         content
     }
 }
@@ -1,7 +1,15 @@
-use crate::{add_event, calendar, database, events};
+use crate::settings::{self, Settings};
+use crate::utils::{background, new_button};
+use crate::{
+    add_event, calendar, database, event_detail, events, ical, tags, transfer, trash, undo,
+};
+use chrono::NaiveDate;
+use iced::keyboard::{self, Key, Modifiers};
 use iced::theme::Theme;
-use iced::widget::{container, scrollable};
-use iced::{executor, Application, Command, Element, Length};
+use iced::widget::{column, container, row, scrollable, text};
+use iced::{executor, time, Alignment, Application, Command, Element, Length, Subscription};
+use std::collections::HashMap;
+use std::time::Duration;
 
 /// Application struct.
 pub struct SinceWhen {
@@ -12,6 +20,18 @@ pub struct SinceWhen {
     calendar: calendar::Calendar,
     events: events::EventsPage,
     add_event: add_event::AddEvent,
+    event_detail: event_detail::EventDetail,
+    trash: trash::TrashPage,
+    transfer: transfer::TransferPage,
+    tag_filter: tags::TagFilter,
+    error: Option<String>,
+    store: database::Store,
+    today: NaiveDate,
+    history: undo::History,
+    /// Whether an undo or redo is running, so the next one waits for it.
+    undoing: bool,
+    toast: Option<String>,
+    trash_retention_days: u32,
 }
 
 /// Application messages.
@@ -26,6 +46,77 @@ pub enum AppMessage {
     CalendarWindow,
     EventsWindow,
     TextEvent(String),
+    Tick,
+    EventsLoaded(database::Result<Vec<events::EventSummary>>),
+    MonthLoaded(
+        i32,
+        u32,
+        database::Result<HashMap<u32, Vec<calendar::DayEvent>>>,
+    ),
+    Mutated,
+    MutationFailed(database::Error),
+    ShowEvent(String),
+    EventLoaded(database::Result<events::EventHistory>),
+    EditOccurrence(i64),
+    OccurrenceDate(String),
+    MoveOccurrence,
+    CancelEdit,
+    DeleteOccurrence(i64),
+    RenameText(String),
+    RenameEvent,
+    Renamed(database::Result<()>),
+    MergeEvent,
+    Merged(database::Result<undo::Change>),
+    ToggleMerge,
+    SelectEvent(String, bool),
+    MergeTarget(String),
+    MergeSelected,
+    Changed(database::Result<undo::Change>),
+    Undo,
+    Redo,
+    Undone(undo::Change, database::Result<undo::Change>),
+    Redone(undo::Change, database::Result<undo::Change>),
+    DismissToast,
+    TrashWindow,
+    TrashLoaded(database::Result<Vec<trash::TrashEntry>>),
+    RestoreEvent(i64),
+    PurgeEvent(i64),
+    Purged(database::Result<usize>),
+    ArchiveEvent(bool),
+    ShowArchived(bool),
+    NoteText(String),
+    QuantityText(String),
+    UnitText(String),
+    CostText(String),
+    MeterText(String),
+    MeterUnitPicked(events::MeterUnit),
+    ReadingText(String),
+    SaveMeter,
+    RemoveMeter,
+    TagsText(String),
+    EventTagsText(String),
+    SaveTags,
+    TagsLoaded(database::Result<Vec<String>>),
+    ToggleTagFilter(String),
+    ClearTagFilter,
+    TargetText(String),
+    SaveTarget,
+    ShowStatColumn(events::StatColumn, bool),
+    TransferWindow,
+    TransferPath(String),
+    DryRun(bool),
+    ExportCsv,
+    ImportCsv,
+    DueDates(bool),
+    ExportIcs,
+    Exported(database::Result<String>),
+    LoadCalendar,
+    CalendarLoaded(database::Result<ical::Calendar>),
+    MapSummary(String, String),
+    FromText(String),
+    ToText(String),
+    ImportCalendar,
+    Imported(database::Result<transfer::ImportReport>),
 }
 
 /// Application pages.
@@ -34,6 +125,9 @@ pub enum Page {
     Calendar,
     Events,
     AddEvent,
+    EventDetail(i64),
+    Trash,
+    Transfer,
 }
 
 /// The SinceWhen application.
@@ -41,31 +135,48 @@ impl Application for SinceWhen {
     type Executor = executor::Default;
     type Message = AppMessage;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = settings::Settings;
 
     /// Creates a new app.
     ///
     /// ### Arguments
-    /// - flags: `()` - No flags are needed.
+    /// - settings: `settings::Settings` - The loaded settings, with the location of the
+    ///   data_base file.
     ///
     /// ### Returns
     /// - `(Self, Command<AppMessage>)` - The app and the command to execute.
-    fn new(_flags: ()) -> (Self, Command<AppMessage>) {
-        let conn = database::setup_connection();
-        database::setup_tables(&conn);
-        // database::insert_test_event(&conn);
-        (
-            Self {
-                day: 0,
-                month: 0,
-                year: 0,
-                current_page: Page::Events,
-                calendar: calendar::Calendar::new(),
-                events: events::EventsPage::new(),
-                add_event: add_event::AddEvent::new(),
-            },
-            Command::none(),
-        )
+    fn new(settings: settings::Settings) -> (Self, Command<AppMessage>) {
+        // Surface a data_base that cannot be opened instead of failing to start,
+        // and keep working in memory so nothing else has to handle a missing store.
+        let (store, error) = match database::Store::open(settings.database_path()) {
+            Ok(store) => (store, None),
+            Err(e) => (
+                database::Store::open_in_memory().expect("in-memory data_base"),
+                Some(format!("{} Changes will not be saved.", e)),
+            ),
+        };
+        let mut app = Self {
+            day: 0,
+            month: 0,
+            year: 0,
+            current_page: Page::Events,
+            calendar: calendar::Calendar::new(),
+            events: events::EventsPage::new(),
+            add_event: add_event::AddEvent::new(),
+            event_detail: event_detail::EventDetail::new(),
+            trash: trash::TrashPage::new(settings.trash_retention_days()),
+            transfer: transfer::TransferPage::new(settings.database_path().with_extension("csv")),
+            tag_filter: tags::TagFilter::new(),
+            error,
+            store,
+            today: today(),
+            history: undo::History::new(),
+            undoing: false,
+            toast: None,
+            trash_retention_days: settings.trash_retention_days(),
+        };
+        let command = Command::batch([app.refresh(), app.purge_trash()]);
+        (app, command)
     }
 
     /// The title of the application.
@@ -85,11 +196,9 @@ impl Application for SinceWhen {
     /// - `Command<AppMessage>` - The command to execute.
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match message {
-            AppMessage::NextMonth => {
-                let _ = self.calendar.update(AppMessage::NextMonth);
-            }
-            AppMessage::PreviousMonth => {
-                let _ = self.calendar.update(AppMessage::PreviousMonth);
+            AppMessage::NextMonth | AppMessage::PreviousMonth => {
+                let _ = self.calendar.update(message);
+                return self.calendar.load(&self.store);
             }
             AppMessage::DayClicked(day, month, year) => {
                 if day == 0 {
@@ -100,48 +209,303 @@ impl Application for SinceWhen {
                 self.year = year;
                 self.current_page = Page::AddEvent;
             }
-            AppMessage::UpdateEvent => {
-                let _ =
-                    self.add_event
-                        .update(AppMessage::UpdateEvent, self.day, self.month, self.year);
-            }
-            AppMessage::AddEvent => {
-                let _ =
-                    self.add_event
-                        .update(AppMessage::AddEvent, self.day, self.month, self.year);
-            }
-            AppMessage::DeleteEvent => {
-                let _ =
-                    self.add_event
-                        .update(AppMessage::DeleteEvent, self.day, self.month, self.year);
-            }
-            AppMessage::TextEvent(event) => {
-                let _ = self.add_event.update(
-                    AppMessage::TextEvent(event),
+            AppMessage::AddEvent
+            | AppMessage::UpdateEvent
+            | AppMessage::DeleteEvent
+            | AppMessage::TextEvent(_)
+            | AppMessage::NoteText(_)
+            | AppMessage::QuantityText(_)
+            | AppMessage::UnitText(_)
+            | AppMessage::CostText(_)
+            | AppMessage::MeterText(_)
+            | AppMessage::TagsText(_) => {
+                return self.add_event.update(
+                    message,
                     self.day,
                     self.month,
                     self.year,
+                    &self.store,
                 );
             }
             AppMessage::CalendarWindow => {
+                self.error = None;
                 self.current_page = Page::Calendar;
             }
             AppMessage::EventsWindow => {
+                self.error = None;
                 self.current_page = Page::Events;
             }
+            AppMessage::Tick => {
+                // Days since change at midnight.
+                if today() != self.today {
+                    return Command::batch([self.refresh(), self.purge_trash()]);
+                }
+            }
+            AppMessage::EventsLoaded(details) => {
+                if let Err(e) = self.events.loaded(details) {
+                    self.error = Some(e.to_string());
+                }
+            }
+            AppMessage::MonthLoaded(year, month, events) => {
+                if let Err(e) = self.calendar.loaded(year, month, events) {
+                    self.error = Some(e.to_string());
+                }
+            }
+            AppMessage::Mutated => {
+                self.error = None;
+                return self.refresh();
+            }
+            AppMessage::MutationFailed(e) => {
+                self.error = Some(e.to_string());
+            }
+            AppMessage::ShowEvent(event) => {
+                self.error = None;
+                return self.event_detail.load_by_name(&self.store, event);
+            }
+            AppMessage::EventLoaded(history) => match self.event_detail.loaded(history) {
+                Ok(id) => self.current_page = Page::EventDetail(id),
+                // The event shown was deleted, for example by undoing its creation.
+                Err(database::Error::IdNotFound(..)) => self.current_page = Page::Events,
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            AppMessage::EditOccurrence(_)
+            | AppMessage::OccurrenceDate(_)
+            | AppMessage::MoveOccurrence
+            | AppMessage::CancelEdit
+            | AppMessage::DeleteOccurrence(_)
+            | AppMessage::RenameText(_)
+            | AppMessage::RenameEvent
+            | AppMessage::MergeEvent
+            | AppMessage::ArchiveEvent(_)
+            | AppMessage::MeterUnitPicked(_)
+            | AppMessage::ReadingText(_)
+            | AppMessage::SaveMeter
+            | AppMessage::RemoveMeter
+            | AppMessage::EventTagsText(_)
+            | AppMessage::SaveTags
+            | AppMessage::TargetText(_)
+            | AppMessage::SaveTarget => {
+                return self.event_detail.update(message, &self.store);
+            }
+            AppMessage::ToggleMerge
+            | AppMessage::SelectEvent(..)
+            | AppMessage::MergeTarget(_)
+            | AppMessage::MergeSelected
+            | AppMessage::ShowArchived(_)
+            | AppMessage::ShowStatColumn(..) => {
+                return self.events.update(message, &self.store);
+            }
+            AppMessage::Renamed(result) => match self.event_detail.renamed(result) {
+                Ok(()) => {
+                    self.error = None;
+                    return self.refresh();
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            AppMessage::Merged(result) => match result {
+                Ok(change) => {
+                    // A merged event is gone, so show the event it was merged into.
+                    if let (Page::EventDetail(_), undo::Change::Merged(merge)) =
+                        (&self.current_page, &change)
+                    {
+                        self.current_page = Page::EventDetail(merge.target.id);
+                    }
+                    self.toast = change.describe_loss();
+                    self.history.push(change);
+                    self.error = None;
+                    return self.refresh();
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            AppMessage::Changed(result) => match result {
+                Ok(change) => {
+                    // Offer to undo anything that lost data.
+                    self.toast = change.describe_loss();
+                    self.history.push(change);
+                    self.error = None;
+                    return self.refresh();
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            AppMessage::Undo if !self.undoing => {
+                self.toast = None;
+                if let Some(change) = self.history.pop_undo() {
+                    self.undoing = true;
+                    let store = self.store.clone();
+                    // A change that could not be reverted goes back, to try again.
+                    return background(
+                        move || (change.revert(&store), change),
+                        |(result, change)| AppMessage::Undone(change, result),
+                    );
+                }
+            }
+            AppMessage::Redo if !self.undoing => {
+                self.toast = None;
+                if let Some(change) = self.history.pop_redo() {
+                    self.undoing = true;
+                    let store = self.store.clone();
+                    return background(
+                        move || (change.revert(&store), change),
+                        |(result, change)| AppMessage::Redone(change, result),
+                    );
+                }
+            }
+            AppMessage::Undone(change, result) => {
+                self.undoing = false;
+                match result {
+                    Ok(undone) => {
+                        self.history.undone(undone);
+                        self.error = None;
+                        return self.refresh();
+                    }
+                    Err(e) => {
+                        self.history.retry_undo(change);
+                        self.error = Some(e.to_string());
+                    }
+                }
+            }
+            AppMessage::Redone(change, result) => {
+                self.undoing = false;
+                match result {
+                    Ok(redone) => {
+                        self.history.redone(redone);
+                        self.error = None;
+                        return self.refresh();
+                    }
+                    Err(e) => {
+                        self.history.retry_redo(change);
+                        self.error = Some(e.to_string());
+                    }
+                }
+            }
+            // Wait for the undo or redo already running, so changes are reverted in order.
+            AppMessage::Undo | AppMessage::Redo => (),
+            AppMessage::DismissToast => {
+                self.toast = None;
+            }
+            AppMessage::TrashWindow => {
+                self.error = None;
+                self.current_page = Page::Trash;
+                return self.trash.load(&self.store);
+            }
+            AppMessage::TrashLoaded(entries) => {
+                if let Err(e) = self.trash.loaded(entries) {
+                    self.error = Some(e.to_string());
+                }
+            }
+            AppMessage::RestoreEvent(_) | AppMessage::PurgeEvent(_) => {
+                return self.trash.update(message, &self.store);
+            }
+            AppMessage::Purged(result) => match result {
+                Ok(0) => (),
+                Ok(_) => return self.refresh(),
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            AppMessage::TagsLoaded(tags) => {
+                if let Err(e) = self.tag_filter.loaded(tags) {
+                    self.error = Some(e.to_string());
+                }
+            }
+            AppMessage::ToggleTagFilter(_) | AppMessage::ClearTagFilter => {
+                self.tag_filter.update(message);
+            }
+            AppMessage::TransferWindow => {
+                self.error = None;
+                self.current_page = Page::Transfer;
+            }
+            AppMessage::TransferPath(_)
+            | AppMessage::DryRun(_)
+            | AppMessage::ExportCsv
+            | AppMessage::ImportCsv
+            | AppMessage::DueDates(_)
+            | AppMessage::ExportIcs
+            | AppMessage::LoadCalendar
+            | AppMessage::MapSummary(..)
+            | AppMessage::FromText(_)
+            | AppMessage::ToText(_)
+            | AppMessage::ImportCalendar => {
+                return self.transfer.update(message, &self.store);
+            }
+            AppMessage::Exported(result) => {
+                if let Err(e) = self.transfer.exported(result) {
+                    self.error = Some(e.to_string());
+                }
+            }
+            AppMessage::CalendarLoaded(calendar) => {
+                if let Err(e) = self.transfer.calendar_loaded(calendar) {
+                    self.error = Some(e.to_string());
+                }
+            }
+            AppMessage::Imported(result) => match self.transfer.imported(result) {
+                Ok(true) => {
+                    self.error = None;
+                    return self.refresh();
+                }
+                Ok(false) => self.error = None,
+                Err(e) => self.error = Some(e.to_string()),
+            },
         }
         Command::none()
     }
 
+    /// Check for a new day once a minute, listen for undo and redo shortcuts,
+    /// and hide the undo offer after a while.
+    ///
+    /// ### Returns
+    /// - `Subscription<AppMessage>`
+    fn subscription(&self) -> Subscription<AppMessage> {
+        let mut subscriptions = vec![
+            time::every(Duration::from_secs(60)).map(|_| AppMessage::Tick),
+            keyboard::on_key_press(undo_shortcut),
+        ];
+        if self.toast.is_some() {
+            subscriptions
+                .push(time::every(Duration::from_secs(10)).map(|_| AppMessage::DismissToast));
+        }
+        Subscription::batch(subscriptions)
+    }
+
     /// The view function.
     ///
     /// ### Returns
     /// - `Element<'static, Self::Message>`
     fn view(&self) -> Element<'static, Self::Message> {
         let content = match self.current_page {
-            Page::Calendar => self.calendar.view(),
-            Page::Events => self.events.view(),
+            Page::Calendar => self.calendar.view(&self.tag_filter, self.events.details()),
+            Page::Events => self.events.view(&self.tag_filter),
             Page::AddEvent => self.add_event.view(self.day, self.month, self.year),
+            Page::EventDetail(_) => self.event_detail.view(),
+            Page::Trash => self.trash.view(),
+            Page::Transfer => self.transfer.view(self.events.details()),
+        };
+        // Show the last data_base error above the page.
+        let content = match &self.error {
+            Some(error) => {
+                let settings = Settings::new();
+                let error_text = text(error.clone())
+                    .size(settings.text_size())
+                    .style(self.theme().palette().danger);
+                column![error_text, content]
+                    .spacing(settings.spacing())
+                    .align_items(Alignment::Center)
+            }
+            None => content,
+        };
+        // Offer to undo the last change that lost data.
+        let content = match &self.toast {
+            Some(toast) => {
+                let settings = Settings::new();
+                let undo_row = row![
+                    text(toast.clone()).size(settings.text_size()),
+                    new_button(AppMessage::Undo, text("Undo"), 100),
+                ]
+                .spacing(settings.spacing())
+                .align_items(Alignment::Center);
+                column![undo_row, content]
+                    .spacing(settings.spacing())
+                    .align_items(Alignment::Center)
+            }
+            None => content,
         };
         container(scrollable(content))
             .width(Length::Fill)
@@ -159,3 +523,72 @@ impl Application for SinceWhen {
         Theme::Nord
     }
 }
+
+impl SinceWhen {
+    /// Reload the events and calendar pages, the tags to filter them by, and the event or trash
+    /// shown, from the data_base in the background.
+    ///
+    /// Called after every change to the data_base and when the date rolls over,
+    /// so the views only ever draw what is cached.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - The loads to run.
+    fn refresh(&mut self) -> Command<AppMessage> {
+        self.today = today();
+        let page = match self.current_page {
+            Page::EventDetail(id) => self.event_detail.load(&self.store, id),
+            Page::Trash => self.trash.load(&self.store),
+            _ => Command::none(),
+        };
+        Command::batch([
+            self.events.load(&self.store),
+            self.calendar.load(&self.store),
+            self.tag_filter.load(&self.store),
+            page,
+        ])
+    }
+
+    /// Purge events that have been in the trash longer than the retention period,
+    /// in the background.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::Purged` when done.
+    fn purge_trash(&self) -> Command<AppMessage> {
+        let store = self.store.clone();
+        let retention_days = self.trash_retention_days;
+        background(
+            move || store.purge_trash(retention_days),
+            AppMessage::Purged,
+        )
+    }
+}
+
+/// Map Ctrl+Z to undo, and Ctrl+Shift+Z or Ctrl+Y to redo.
+///
+/// ### Arguments
+/// - key: `Key` - The key pressed.
+/// - modifiers: `Modifiers` - The modifier keys held.
+///
+/// ### Returns
+/// - `Option<AppMessage>`
+fn undo_shortcut(key: Key, modifiers: Modifiers) -> Option<AppMessage> {
+    if !modifiers.command() {
+        return None;
+    }
+    match key.as_ref() {
+        Key::Character(c) if c.eq_ignore_ascii_case("z") && modifiers.shift() => {
+            Some(AppMessage::Redo)
+        }
+        Key::Character(c) if c.eq_ignore_ascii_case("z") => Some(AppMessage::Undo),
+        Key::Character(c) if c.eq_ignore_ascii_case("y") => Some(AppMessage::Redo),
+        _ => None,
+    }
+}
+
+/// Today's local date.
+///
+/// ### Returns
+/// - `NaiveDate`
+fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
@@ -1,22 +1,44 @@
 use chrono::{Datelike, FixedOffset, Utc};
 use iced::alignment::{Horizontal, Vertical};
-use iced::theme::Button::{Primary, Secondary};
-use iced::widget::{button, row, text, Column, Row};
+use iced::theme::{
+    self,
+    Button::{Primary, Secondary},
+};
+use iced::widget::{button, row, text, tooltip, Column, Row};
 use iced::{Alignment, Command};
 use num_traits::cast::FromPrimitive;
+use std::collections::HashMap;
 
 use crate::{
     app::AppMessage,
-    database::events_by_year_month,
+    database::{Result, Store},
+    events::EventSummary,
     settings::Settings,
-    utils::{get_date, last_day_of_month, make_new_row, new_button},
+    tags::TagFilter,
+    utils::{
+        background, get_date, last_day_of_month, make_new_row, new_button, project_occurrences,
+    },
 };
 
+/// An event that happened on a day of the displayed month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayEvent {
+    pub name: String,
+    /// The note recorded with the occurrence, if any.
+    pub note: Option<String>,
+    /// The event's tags, sorted.
+    pub tags: Vec<String>,
+}
+
 /// The state of the Calendar.
-#[derive(Debug, Clone, Copy)]
+///
+/// Holds the events of the displayed month, so drawing it never touches the data_base.
+#[derive(Debug, Clone)]
 pub struct Calendar {
     month: u32,
     year: i32,
+    events: HashMap<u32, Vec<DayEvent>>,
+    loading: bool,
 }
 
 /// Default Calendar implementation.
@@ -33,7 +55,54 @@ impl<'a> Calendar {
         let now = chrono::Utc::now();
         let month = now.month();
         let year = now.year();
-        Self { month, year }
+        Self {
+            month,
+            year,
+            events: HashMap::new(),
+            loading: false,
+        }
+    }
+
+    /// Reload the events of the displayed month from the data_base in the background.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::MonthLoaded` when done.
+    pub fn load(&mut self, store: &Store) -> Command<AppMessage> {
+        self.loading = true;
+        let store = store.clone();
+        let (year, month) = (self.year, self.month);
+        background(
+            move || store.events_by_year_month(year, month),
+            move |events| AppMessage::MonthLoaded(year, month, events),
+        )
+    }
+
+    /// Show freshly loaded events for a month.
+    ///
+    /// Results for a month other than the displayed one are stale and ignored.
+    ///
+    /// ### Arguments
+    /// - year: `i32` - The year that was loaded.
+    /// - month: `u32` - The month that was loaded.
+    /// - events: `Result<HashMap<u32, Vec<DayEvent>>>` - The result of `load`.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from loading, if any.
+    pub fn loaded(
+        &mut self,
+        year: i32,
+        month: u32,
+        events: Result<HashMap<u32, Vec<DayEvent>>>,
+    ) -> Result<()> {
+        if (year, month) != (self.year, self.month) {
+            return Ok(());
+        }
+        self.loading = false;
+        self.events = events?;
+        Ok(())
     }
 
     /// Updates the Calendar State via messages.
@@ -67,8 +136,10 @@ impl<'a> Calendar {
                     self.month += 1;
                 }
             }
-            _ => {}
+            _ => return Command::none(),
         }
+        // The events shown belong to the previous month until the new month loads.
+        self.events.clear();
         Command::none()
     }
 
@@ -76,11 +147,15 @@ impl<'a> Calendar {
     ///
     /// ### Returns
     /// - `Row<'a, AppMessage>` - The instructions row.
-    fn instruction_row(self) -> Row<'a, AppMessage> {
+    fn instruction_row(&self) -> Row<'a, AppMessage> {
         // Text to explain what to do.
         let settings = Settings::new();
-        let instructions =
-            text("Click a day to add or update an event.").size(settings.text_size());
+        let instructions = text(if self.loading {
+            "Loading..."
+        } else {
+            "Click a day to add or update an event."
+        })
+        .size(settings.text_size());
         // Create a row for current month, prev and next month buttons.
         let instruction_row = row![instructions]
             .spacing(settings.spacing())
@@ -92,7 +167,7 @@ impl<'a> Calendar {
     ///
     /// ### Returns
     /// - `Row<'a, AppMessage>` - The navigation row.
-    fn nav_row(self) -> Row<'a, AppMessage> {
+    fn nav_row(&self) -> Row<'a, AppMessage> {
         let settings = Settings::new();
         let prev_button = new_button(AppMessage::PreviousMonth, text("<"), settings.text_size());
         // Display the current month and year.
@@ -111,11 +186,51 @@ impl<'a> Calendar {
             .align_items(Vertical::Center.into())
     }
 
+    /// Get the occurrences expected from today on in the displayed month.
+    ///
+    /// ### Arguments
+    /// - summaries: `&[EventSummary]` - The events, with their predictions.
+    /// - filter: `&TagFilter` - The tags picked to filter the events by.
+    ///
+    /// ### Returns
+    /// - `HashMap<u32, Vec<&'b EventSummary>>` - `{day: [event,...]}`, leaving out archived events.
+    fn projections<'b>(
+        &self,
+        summaries: &'b [EventSummary],
+        filter: &TagFilter,
+    ) -> HashMap<u32, Vec<&'b EventSummary>> {
+        let today = chrono::Local::now().date_naive();
+        let first = get_date(self.year, self.month, 1).max(today);
+        let last = get_date(
+            self.year,
+            self.month,
+            last_day_of_month(self.year, self.month) as u32,
+        );
+        let mut projections: HashMap<u32, Vec<&EventSummary>> = HashMap::new();
+        for event in summaries
+            .iter()
+            .filter(|event| !event.archived && filter.matches(&event.tags))
+        {
+            if let Some(prediction) = &event.prediction {
+                for date in project_occurrences(prediction, first, last) {
+                    projections.entry(date.day()).or_default().push(event);
+                }
+            }
+        }
+        projections
+    }
+
     /// Creates the Calendar view.
     ///
+    /// Occurrences expected in the future are marked with a `~`.
+    ///
+    /// ### Arguments
+    /// - filter: `&TagFilter` - The tags picked to filter the events by.
+    /// - summaries: `&[EventSummary]` - The events, with their predictions.
+    ///
     /// ### Returns
     /// - `Column<'a, AppMessage>` - The Calendar view.
-    fn calendar(self) -> Column<'a, AppMessage> {
+    fn calendar(&self, filter: &TagFilter, summaries: &[EventSummary]) -> Column<'a, AppMessage> {
         let settings = Settings::new();
         // Create a column to hold the Calendar.
         let mut calendar = Column::new()
@@ -132,7 +247,8 @@ impl<'a> Calendar {
         // Variables to hold the current day and the day to display.
         let mut day: u32;
         let mut print_day: String;
-        let current_events = events_by_year_month(self.year, self.month).expect("events");
+        let current_events = &self.events;
+        let projections = self.projections(summaries, filter);
         let tz_offset = FixedOffset::west_opt(8 * 60 * 60).unwrap();
         let today = Utc::now().with_timezone(&tz_offset);
         let (today_day, today_month, today_year) = (today.day(), today.month(), today.year());
@@ -156,31 +272,56 @@ impl<'a> Calendar {
                 day = 0;
                 print_day = " ".to_string()
             };
+            // Notes of the day's occurrences, shown on hover.
+            let mut notes = Vec::new();
             if current_events.contains_key(&day) {
                 if let Some(event_vec) = current_events.get(&day) {
-                    for event in event_vec {
-                        print_day = print_day + "\n" + event;
+                    for event in event_vec.iter().filter(|event| filter.matches(&event.tags)) {
+                        print_day = print_day + "\n" + &event.name;
+                        if let Some(note) = &event.note {
+                            notes.push(format!("{}: {}", event.name, note));
+                        }
                     }
                 }
             };
-            calendar_row = calendar_row.push(
-                button(
-                    text(print_day)
-                        .vertical_alignment(Vertical::Top)
-                        .horizontal_alignment(Horizontal::Left)
-                        .size(15),
-                )
-                .on_press(AppMessage::DayClicked(day, self.month, self.year))
-                .style(
-                    if (day, self.month, self.year) == (today_day, today_month, today_year) {
-                        Primary
-                    } else {
-                        Secondary
-                    },
-                )
-                .width(settings.calendar_width())
-                .height(settings.calendar_width()),
-            );
+            // Ghost markers for expected occurrences.
+            for event in projections.get(&day).into_iter().flatten() {
+                print_day = print_day + "\n~" + &event.name;
+                if let Some(prediction) = &event.prediction {
+                    notes.push(format!(
+                        "{}: expected every {} days",
+                        event.name, prediction.interval
+                    ));
+                }
+            }
+            let day_button = button(
+                text(print_day)
+                    .vertical_alignment(Vertical::Top)
+                    .horizontal_alignment(Horizontal::Left)
+                    .size(15),
+            )
+            .on_press(AppMessage::DayClicked(day, self.month, self.year))
+            .style(
+                if (day, self.month, self.year) == (today_day, today_month, today_year) {
+                    Primary
+                } else {
+                    Secondary
+                },
+            )
+            .width(settings.calendar_width())
+            .height(settings.calendar_width());
+            if notes.is_empty() {
+                calendar_row = calendar_row.push(day_button);
+            } else {
+                calendar_row = calendar_row.push(
+                    tooltip(
+                        day_button,
+                        text(notes.join("\n")).size(15),
+                        tooltip::Position::FollowCursor,
+                    )
+                    .style(theme::Container::Box),
+                );
+            }
             // If the current day is a Saturday, push the current row and start a new week.
             if (i + 1) % 7 == 0 {
                 calendar = calendar.push(calendar_row);
@@ -195,15 +336,23 @@ impl<'a> Calendar {
     ///
     /// The Calendar is a 7 x 6 grid of day buttons.
     ///
+    /// ### Arguments
+    /// - filter: `&TagFilter` - The tags picked to filter the events by.
+    /// - summaries: `&[EventSummary]` - The events, to mark when they are expected next.
+    ///
     /// ### Returns
     /// - `Element<'a, AppMessage>` - The Calendar page.
-    pub fn view(self) -> Column<'a, AppMessage> {
+    pub fn view(&self, filter: &TagFilter, summaries: &[EventSummary]) -> Column<'a, AppMessage> {
         let settings = Settings::new();
-        // Create a column to hold the calendar, nav buttons, and instructions.
-        let content = Column::new()
+        // Create a column to hold the calendar, nav buttons, tags, and instructions.
+        let mut content = Column::new()
             .push(self.instruction_row())
-            .push(self.nav_row())
-            .push(self.calendar())
+            .push(self.nav_row());
+        if let Some(tag_row) = filter.view() {
+            content = content.push(tag_row);
+        }
+        let content = content
+            .push(self.calendar(filter, summaries))
             .push(new_button(
                 AppMessage::EventsWindow,
                 text("Events"),
@@ -0,0 +1,522 @@
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::{
+    database::{Error, Result, Store},
+    events::{OccurrenceDetails, StatColumn},
+    ical,
+    settings::DATABASE_FLAG,
+    transfer, utils,
+};
+use std::path::PathBuf;
+
+/// How to use the command line interface.
+pub const USAGE: &str = "\
+Usage: since_when [--database <path>] [<command>]
+
+Without a command, the app window opens.
+
+Commands:
+  add <event> [--date YYYY-MM-DD] [--note <note>]   Add a new event
+  log <event> [--date YYYY-MM-DD] [--note <note>]   Log another occurrence of an event
+  list [--all] [--json]                             List events, with archived events if --all
+  delete <event>                                    Move an event to the trash
+  show <event> [--json]                             Show every occurrence of an event
+  export [<file>] [--ics [--due]]                   Export every occurrence as CSV, to stdout without a file
+  import <file> [--dry-run]                         Import occurrences from CSV, creating missing events
+  import <file> --ics [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--map <summary>=<event>]... [--dry-run]
+                                                    Import occurrences from an iCalendar file
+  help                                              Show this message
+
+Arguments after -- are never read as options, as in: since_when add -- \"-5 min plank\"
+Occurrences are dated today unless --date is given. With --json, list and show print JSON.
+With --ics, export writes an iCalendar file instead, adding due dates if --due.
+With --dry-run, import reports what it would add without saving anything.
+With --ics, import reads an iCalendar file, up to today unless --to is given. Calendar events
+are imported under their own title unless mapped to an event with --map, or skipped with
+--map <summary>=.
+";
+
+/// A command run from the command line instead of opening the app window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subcommand {
+    Add {
+        event: String,
+        date: NaiveDate,
+        note: Option<String>,
+    },
+    Log {
+        event: String,
+        date: NaiveDate,
+        note: Option<String>,
+    },
+    List {
+        all: bool,
+        json: bool,
+    },
+    Delete {
+        event: String,
+    },
+    Show {
+        event: String,
+        json: bool,
+    },
+    Export {
+        file: Option<PathBuf>,
+        ics: bool,
+        due: bool,
+    },
+    Import {
+        file: PathBuf,
+        dry_run: bool,
+        /// Whether the file is an iCalendar file rather than CSV.
+        ics: bool,
+        /// The first day imported from a calendar.
+        from: Option<NaiveDate>,
+        /// The last day imported from a calendar.
+        to: NaiveDate,
+        /// `(summary, event)`, the event to import each calendar summary as.
+        map: Vec<(String, String)>,
+    },
+    Help,
+}
+
+/// Parse the command line arguments.
+///
+/// `--database` is left for `Settings::load` to read.
+///
+/// ### Arguments
+/// - args: `impl IntoIterator<Item = String>` - The command line arguments, without the program name.
+/// - today: `NaiveDate` - The date of occurrences without `--date`, and the last day
+///   imported from a calendar without `--to`.
+///
+/// ### Returns
+/// - `Result<(Option<PathBuf>, Option<Subcommand>)>` - The `--database` path, if given, and the
+///   command to run, `None` to open the app window. `Error::InvalidInput` if the arguments make
+///   no sense.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::cli::{parse, Subcommand};
+/// # use since_when_lib::utils::get_date;
+/// let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+/// let today = get_date(2026, 10, 16);
+///
+/// assert_eq!(
+///     parse(args(&["--database", "/tmp/events.db"]), today),
+///     Ok((Some("/tmp/events.db".into()), None))
+/// );
+/// assert_eq!(
+///     parse(args(&["add", "Oil change", "--date", "2026-10-01"]), today),
+///     Ok((None, Some(Subcommand::Add {
+///         event: "Oil change".to_string(),
+///         date: get_date(2026, 10, 1),
+///         note: None,
+///     })))
+/// );
+/// assert_eq!(
+///     parse(args(&["log", "Haircut", "--note=short"]), today),
+///     Ok((None, Some(Subcommand::Log {
+///         event: "Haircut".to_string(),
+///         date: today,
+///         note: Some("short".to_string()),
+///     })))
+/// );
+/// let (database, _) = parse(args(&["log", "Haircut", "--database=/tmp/events.db"]), today).unwrap();
+/// assert_eq!(database, Some("/tmp/events.db".into()));
+/// assert_eq!(
+///     parse(args(&["log", "--note", "short", "--", "-5 min plank"]), today),
+///     Ok((None, Some(Subcommand::Log {
+///         event: "-5 min plank".to_string(),
+///         date: today,
+///         note: Some("short".to_string()),
+///     })))
+/// );
+/// assert!(parse(args(&["delete", "--", "--help"]), today).is_ok());
+/// assert!(parse(args(&["add"]), today).is_err());
+/// assert!(parse(args(&["list", "--date", "2026-10-01"]), today).is_err());
+/// assert!(parse(args(&["add", "Oil change", "--date", "yesterday"]), today).is_err());
+/// assert_eq!(
+///     parse(args(&["list", "--json"]), today),
+///     Ok((None, Some(Subcommand::List { all: false, json: true })))
+/// );
+/// assert!(parse(args(&["delete", "Haircut", "--json"]), today).is_err());
+/// assert_eq!(
+///     parse(args(&["import", "events.csv", "--dry-run"]), today),
+///     Ok((None, Some(Subcommand::Import {
+///         file: "events.csv".into(),
+///         dry_run: true,
+///         ics: false,
+///         from: None,
+///         to: today,
+///         map: Vec::new(),
+///     })))
+/// );
+/// assert_eq!(
+///     parse(args(&["import", "cal.ics", "--ics", "--from=2020-01-01", "--map", "Barber=Haircut"]), today),
+///     Ok((None, Some(Subcommand::Import {
+///         file: "cal.ics".into(),
+///         dry_run: false,
+///         ics: true,
+///         from: Some(get_date(2020, 1, 1)),
+///         to: today,
+///         map: vec![("Barber".to_string(), "Haircut".to_string())],
+///     })))
+/// );
+/// assert!(parse(args(&["import", "events.csv", "--map", "Barber=Haircut"]), today).is_err());
+/// assert_eq!(
+///     parse(args(&["export", "events.ics", "--ics", "--due"]), today),
+///     Ok((None, Some(Subcommand::Export { file: Some("events.ics".into()), ics: true, due: true })))
+/// );
+/// assert!(parse(args(&["export", "--due"]), today).is_err());
+/// assert!(parse(args(&["frobnicate"]), today).is_err());
+/// ```
+pub fn parse(
+    args: impl IntoIterator<Item = String>,
+    today: NaiveDate,
+) -> Result<(Option<PathBuf>, Option<Subcommand>)> {
+    let mut database = None;
+    let mut positional = Vec::new();
+    let mut date = None;
+    let mut note = None;
+    let mut all = false;
+    let mut json = false;
+    let mut dry_run = false;
+    let mut ics = false;
+    let mut due = false;
+    let mut from = None;
+    let mut to = None;
+    let mut map = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Everything after `--` is positional, for event names that look like options.
+        if arg == "--" {
+            positional.extend(args.by_ref());
+            break;
+        }
+        // Options take their value as the next argument or after an `=`.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| Error::InvalidInput(format!("{} needs a value.\n\n{}", flag, USAGE)))
+        };
+        match flag.as_str() {
+            DATABASE_FLAG => database = Some(PathBuf::from(value()?)),
+            "--date" => date = Some(parse_date(&value()?)?),
+            "--from" => from = Some(parse_date(&value()?)?),
+            "--to" => to = Some(parse_date(&value()?)?),
+            "--map" => {
+                let text = value()?;
+                let (summary, event) = text.split_once('=').ok_or_else(|| {
+                    Error::InvalidInput(format!("\"{}\" is not <summary>=<event>.", text))
+                })?;
+                map.push((summary.to_string(), event.to_string()));
+            }
+            "--note" => note = Some(value()?),
+            "--all" => all = true,
+            "--json" => json = true,
+            "--dry-run" => dry_run = true,
+            "--ics" => ics = true,
+            "--due" => due = true,
+            "-h" | "--help" => positional.insert(0, "help".to_string()),
+            flag if flag.starts_with("--") => {
+                return Err(Error::InvalidInput(format!(
+                    "Unknown option {}.\n\n{}",
+                    flag, USAGE
+                )))
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let Some(command) = positional.first() else {
+        return Ok((database, None));
+    };
+    let invalid = || Error::InvalidInput(USAGE.to_string());
+    // The event name, for the commands that take exactly one.
+    let event = || match &positional[1..] {
+        [event] => Ok(event.clone()),
+        _ => Err(invalid()),
+    };
+    let dated = matches!(command.as_str(), "add" | "log");
+    if ((date.is_some() || note.is_some()) && !dated)
+        || (all && command != "list")
+        || (json && !matches!(command.as_str(), "list" | "show"))
+        || (dry_run && command != "import")
+        || (ics && !matches!(command.as_str(), "export" | "import"))
+        || (due && (!ics || command != "export"))
+        || ((from.is_some() || to.is_some() || !map.is_empty()) && (!ics || command != "import"))
+    {
+        return Err(invalid());
+    }
+    let date = date.unwrap_or(today);
+    let subcommand = match command.as_str() {
+        "add" => Subcommand::Add {
+            event: event()?,
+            date,
+            note,
+        },
+        "log" => Subcommand::Log {
+            event: event()?,
+            date,
+            note,
+        },
+        "list" if positional.len() == 1 => Subcommand::List { all, json },
+        "delete" => Subcommand::Delete { event: event()? },
+        "show" => Subcommand::Show {
+            event: event()?,
+            json,
+        },
+        "export" => match &positional[1..] {
+            [] => Subcommand::Export {
+                file: None,
+                ics,
+                due,
+            },
+            [file] => Subcommand::Export {
+                file: Some(file.into()),
+                ics,
+                due,
+            },
+            _ => return Err(invalid()),
+        },
+        "import" => match &positional[1..] {
+            [file] => Subcommand::Import {
+                file: file.into(),
+                dry_run,
+                ics,
+                from,
+                to: to.unwrap_or(today),
+                map,
+            },
+            _ => return Err(invalid()),
+        },
+        "help" => Subcommand::Help,
+        _ => return Err(invalid()),
+    };
+    Ok((database, Some(subcommand)))
+}
+
+/// Run a command against the data_base.
+///
+/// ### Arguments
+/// - subcommand: `Subcommand` - The command to run.
+/// - store: `&Store` - The events data_base.
+///
+/// ### Returns
+/// - `Result<String>` - What to print.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::cli::{run, Subcommand};
+/// # use since_when_lib::database::Store;
+/// # use since_when_lib::utils::get_date;
+/// let store = Store::open_in_memory().unwrap();
+/// let log = |day| Subcommand::Log {
+///     event: "Oil change".to_string(),
+///     date: get_date(2026, 7, day),
+///     note: None,
+/// };
+/// run(
+///     Subcommand::Add {
+///         event: "Oil change".to_string(),
+///         date: get_date(2026, 7, 1),
+///         note: Some("5W-30".to_string()),
+///     },
+///     &store,
+/// )
+/// .unwrap();
+/// run(log(31), &store).unwrap();
+/// assert!(run(log(31), &store).is_err());
+///
+/// let show = |json| Subcommand::Show { event: "Oil change".to_string(), json };
+/// assert_eq!(
+///     run(show(false), &store).unwrap(),
+///     "Oil change\n2026-07-01  ---      5W-30\n2026-07-31  30 days\n"
+/// );
+/// let history: serde_json::Value = serde_json::from_str(&run(show(true), &store).unwrap()).unwrap();
+/// assert_eq!(history["occurrences"][0]["details"]["note"], "5W-30");
+///
+/// let list = run(Subcommand::List { all: false, json: false }, &store).unwrap();
+/// assert!(list.starts_with("Oil change") && list.contains("avg 30 days"));
+///
+/// // JSON for dashboards and `jq`.
+/// let list = run(Subcommand::List { all: false, json: true }, &store).unwrap();
+/// let summaries: serde_json::Value = serde_json::from_str(&list).unwrap();
+/// assert_eq!(summaries[0]["name"], "Oil change");
+/// assert_eq!(summaries[0]["last"], "2026-07-31");
+/// assert_eq!(summaries[0]["occurrences"], 2);
+/// assert_eq!(summaries[0]["intervals"]["mean"], 30.0);
+///
+/// run(Subcommand::Delete { event: "Oil change".to_string() }, &store).unwrap();
+/// assert_eq!(run(Subcommand::List { all: true, json: false }, &store).unwrap(), "No events yet.\n");
+/// assert_eq!(run(Subcommand::List { all: true, json: true }, &store).unwrap(), "[]\n");
+/// ```
+pub fn run(subcommand: Subcommand, store: &Store) -> Result<String> {
+    match subcommand {
+        Subcommand::Add { event, date, note } => {
+            let details = OccurrenceDetails {
+                note,
+                ..Default::default()
+            };
+            store.add_event_with(&event, date, &details)?;
+            Ok(format!("Added \"{}\" on {}.\n", event.trim(), date))
+        }
+        Subcommand::Log { event, date, note } => {
+            let details = OccurrenceDetails {
+                note,
+                ..Default::default()
+            };
+            store.update_event_with(&event, date, &details)?;
+            Ok(format!("Logged \"{}\" on {}.\n", event.trim(), date))
+        }
+        Subcommand::List { all, json } => {
+            let events: Vec<_> = utils::event_details(store)?
+                .into_iter()
+                .filter(|event| all || !event.archived)
+                .collect();
+            if json {
+                return Ok(to_json(&events));
+            }
+            if events.is_empty() {
+                return Ok("No events yet.\n".to_string());
+            }
+            let names: Vec<String> = events
+                .iter()
+                .map(|event| match event.archived {
+                    true => format!("{} (archived)", event.name),
+                    false => event.name.clone(),
+                })
+                .collect();
+            let width = names.iter().map(|name| name.chars().count()).max();
+            let mut output = String::new();
+            for (name, event) in names.iter().zip(&events) {
+                let plural = if event.days_since != 1 { "s" } else { "" };
+                let ago = format!("{} day{} ago", event.days_since, plural);
+                output += &format!(
+                    "{:<width$}  {:>14}  avg {}\n",
+                    name,
+                    ago,
+                    StatColumn::Mean.format(event.intervals.as_ref()),
+                    width = width.unwrap_or_default(),
+                );
+            }
+            Ok(output)
+        }
+        Subcommand::Delete { event } => {
+            store.delete_event(&event)?;
+            Ok(format!("Moved \"{}\" to the trash.\n", event.trim()))
+        }
+        Subcommand::Show { event, json } => {
+            let history = store.event_history(store.get_event_id(&event)?)?;
+            if json {
+                return Ok(to_json(&history));
+            }
+            let mut output = format!("{}\n", history.name);
+            let mut previous: Option<NaiveDate> = None;
+            for occurrence in &history.occurrences {
+                let interval = match previous {
+                    Some(previous) => {
+                        let days = (occurrence.date - previous).num_days();
+                        format!("{} day{}", days, if days != 1 { "s" } else { "" })
+                    }
+                    None => "---".to_string(),
+                };
+                let line = format!(
+                    "{}  {:<7}  {}",
+                    occurrence.date,
+                    interval,
+                    occurrence.details.note.as_deref().unwrap_or_default()
+                );
+                output += line.trim_end();
+                output += "\n";
+                previous = Some(occurrence.date);
+            }
+            Ok(output)
+        }
+        Subcommand::Export {
+            file: None,
+            ics: false,
+            ..
+        } => transfer::export_csv(store),
+        Subcommand::Export {
+            file: None,
+            ics: true,
+            due,
+        } => ical::export_ics(store, due, Utc::now().naive_utc()),
+        Subcommand::Export {
+            file: Some(file),
+            ics: false,
+            ..
+        } => {
+            let count = transfer::export_file(store, &file)?;
+            Ok(format!(
+                "{}\n",
+                transfer::describe_export(count, "occurrence", &file)
+            ))
+        }
+        Subcommand::Export {
+            file: Some(file),
+            ics: true,
+            due,
+        } => {
+            let count = ical::export_ics_file(store, &file, due)?;
+            Ok(format!(
+                "{}\n",
+                transfer::describe_export(count, "calendar event", &file)
+            ))
+        }
+        Subcommand::Import {
+            file,
+            dry_run,
+            ics: false,
+            ..
+        } => Ok(transfer::import_file(store, &file, dry_run)?.to_string()),
+        Subcommand::Import {
+            file,
+            dry_run,
+            ics: true,
+            from,
+            to,
+            map,
+        } => {
+            let calendar = ical::Calendar::read(&file)?;
+            let mapping = map.into_iter().collect();
+            Ok(calendar
+                .import(store, &mapping, from, to, dry_run)?
+                .to_string())
+        }
+        Subcommand::Help => Ok(USAGE.to_string()),
+    }
+}
+
+/// Parse a date given on the command line.
+///
+/// ### Arguments
+/// - text: `&str` - The date, as YYYY-MM-DD.
+///
+/// ### Returns
+/// - `Result<NaiveDate>` - `Error::InvalidInput` if the text is not a date.
+fn parse_date(text: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| Error::InvalidInput(format!("\"{}\" is not a YYYY-MM-DD date.", text)))
+}
+
+/// Format a value as pretty printed JSON.
+///
+/// ### Arguments
+/// - value: `&impl Serialize` - The value to format.
+///
+/// ### Returns
+/// - `String` - The JSON, ending in a newline.
+fn to_json(value: &impl Serialize) -> String {
+    // Only maps with non-string keys fail to serialize, and none are printed.
+    let json = serde_json::to_string_pretty(value).expect("serializable as JSON");
+    format!("{}\n", json)
+}
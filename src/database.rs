@@ -1,19 +1,110 @@
-use crate::events::EventOccurrence;
-use log::{error, info};
-use rusqlite::{params, Connection, Result, Statement};
+use crate::calendar::DayEvent;
+use crate::events::{
+    EventHistory, EventOccurrence, Merge, Meter, MeterUnit, Occurrence, OccurrenceDetails,
+};
+use crate::migrations;
+use crate::transfer::{ImportReport, ImportRow, RowError};
+use crate::trash::TrashEntry;
+use crate::utils::{get_date, last_day_of_month};
+use chrono::{Datelike, NaiveDate};
+use log::info;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, ErrorCode, Row, Statement};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::{fmt, fs};
+
+/// Errors returned by data_base operations.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// No event with the given name exists.
+    NotFound(String),
+    /// An event with the given name already exists.
+    DuplicateName(String),
+    /// An event with the given name is in the trash.
+    Trashed(String),
+    /// A data_base constraint was violated.
+    Constraint(String),
+    /// The data_base file could not be opened.
+    Open(String),
+    /// A value entered by the user could not be used.
+    InvalidInput(String),
+    /// No event or occurrence has the given id.
+    IdNotFound(&'static str, i64),
+    /// The data_base was created by a newer version of the app.
+    UnsupportedVersion(u32),
+    /// Any other SQLite failure.
+    Sqlite(String),
+}
+
+/// Result type for data_base operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(name) => write!(f, "No event named \"{}\".", name),
+            Error::DuplicateName(name) => write!(f, "An event named \"{}\" already exists.", name),
+            Error::Trashed(name) => write!(
+                f,
+                "An event named \"{}\" is in the trash. Restore it from the Trash page.",
+                name
+            ),
+            Error::InvalidInput(e) => write!(f, "{}", e),
+            Error::IdNotFound(kind, id) => write!(f, "No {} with id {}.", kind, id),
+            Error::Constraint(e) => write!(f, "Constraint violated: {}", e),
+            Error::Open(e) => write!(f, "Could not open the data_base: {}", e),
+            Error::UnsupportedVersion(v) => write!(
+                f,
+                "The data_base uses schema version {}, which is newer than this app supports.",
+                v
+            ),
+            Error::Sqlite(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(ErrorCode::ConstraintViolation) => Error::Constraint(e.to_string()),
+            Some(ErrorCode::CannotOpen) => Error::Open(e.to_string()),
+            _ => Error::Sqlite(e.to_string()),
+        }
+    }
+}
+
+impl ToSql for MeterUnit {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        self.as_str().to_sql()
+    }
+}
+
+impl FromSql for MeterUnit {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let unit = value.as_str()?;
+        MeterUnit::parse(unit).ok_or_else(|| FromSqlError::Other(format!("{:?}", unit).into()))
+    }
+}
 
 /// Setup rusqlite connection.
 ///
+/// ### Arguments
+/// - path: `&Path` - The location of the data_base file. Missing directories are created.
+///
 /// ### Returns
-/// - `Connection` - The connection to the data_base.
-pub fn setup_connection() -> Connection {
-    match Connection::open("since_when.db") {
-        Ok(conn) => conn,
-        Err(e) => {
-            panic!("Error opening data_base {}", e);
-        }
+/// - `Result<Connection>` - The connection to the data_base.
+pub fn setup_connection(path: &Path) -> Result<Connection> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| Error::Open(format!("{}: {}", dir.display(), e)))?;
     }
+    let conn = Connection::open(path).map_err(|e| Error::Open(e.to_string()))?;
+    // Cascading deletes depend on foreign keys, which SQLite builds may leave off by default.
+    conn.pragma_update(None, "foreign_keys", true)?;
+    Ok(conn)
 }
 
 /// Prepare a SQL statement.
@@ -23,366 +114,1588 @@ pub fn setup_connection() -> Connection {
 /// - stmt: `&'a str` - The SQL statement to prepare.
 ///
 /// ### Returns
-/// - `Statement<'a>`
-pub fn prepare_stmt<'a>(conn: &'a Connection, stmt: &'a str) -> Statement<'a> {
-    match conn.prepare(stmt) {
-        Ok(statement) => statement,
-        Err(e) => {
-            panic!("Error preparing statement: {}", e);
-        }
-    }
+/// - `Result<Statement<'a>>`
+pub fn prepare_stmt<'a>(conn: &'a Connection, stmt: &'a str) -> Result<Statement<'a>> {
+    Ok(conn.prepare(stmt)?)
 }
 
-/// Setup the data_base tables.
+/// Setup the data_base tables, upgrading older data_bases to the current schema.
 ///
 /// ### Arguments
 /// - `&Connection` - The connection to the data_base.
 ///
 /// ### Returns
-/// - `()`
-pub fn setup_tables(conn: &Connection) {
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS events (
-              id              INTEGER PRIMARY KEY,
-              name            TEXT NOT NULL UNIQUE
-              );",
-        params![],
-    ) {
-        Ok(_) => {
-            info!("Created table events.");
-        }
-        Err(e) => {
-            error!("Error creating table: {}", e);
-        }
+/// - `Result<()>`
+pub fn setup_tables(conn: &Connection) -> Result<()> {
+    let version = migrations::migrate(conn)?;
+    info!(
+        "Data_base schema at version {} (was {}).",
+        migrations::LATEST_VERSION,
+        version
+    );
+    Ok(())
+}
+
+/// Get the id of the event.
+///
+/// ### Arguments
+/// - conn: `&Connection` - The data_base connection.
+/// - event: `&str` - The name of the event. Whitespace around it is ignored, as it is when
+///   the event is added.
+///
+/// ### Returns
+/// - `Result<i64>` - The id of the event, or `Error::NotFound` if there is no such event
+///   or it is in the trash.
+fn event_id(conn: &Connection, event: &str) -> Result<i64> {
+    let event = event.trim();
+    info!("Getting event id for {:?}", event);
+    let mut id_stmt = prepare_stmt(
+        conn,
+        "SELECT id FROM events WHERE name = ?1 AND deleted_at IS NULL;",
+    )?;
+    id_stmt
+        .query_row(params![event], |row| row.get(0))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::NotFound(event.to_string()),
+            e => e.into(),
+        })
+}
+
+/// Check the name of a new event.
+///
+/// ### Arguments
+/// - name: `&str` - The name as entered.
+///
+/// ### Returns
+/// - `Result<&str>` - The name without surrounding whitespace, or `Error::InvalidInput` if
+///   nothing is left.
+fn event_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::InvalidInput("An event needs a name.".to_string()));
     }
-    match conn.execute(
-        "CREATE TABLE IF NOT EXISTS occurrences (
-              event_id        INTEGER,
-              year            INTEGER NOT NULL,
-              month           INTEGER NOT NULL,
-              day             INTEGER NOT NULL,
-              FOREIGN KEY(event_id) REFERENCES events(id)
-              );",
-        params![],
-    ) {
-        Ok(_) => {
-            info!("Created table occurrences.");
-        }
-        Err(e) => {
-            error!("Error creating table: {}", e);
-        }
+    Ok(name)
+}
+
+/// Explain why a name cannot be used.
+///
+/// ### Arguments
+/// - conn: `&Connection` - The data_base connection.
+/// - name: `&str` - The name that broke the unique constraint.
+///
+/// ### Returns
+/// - `Error` - `Error::Trashed` if the event with that name is in the trash,
+///   otherwise `Error::DuplicateName`.
+fn name_taken(conn: &Connection, name: &str) -> Error {
+    let trashed = conn.query_row(
+        "SELECT deleted_at IS NOT NULL FROM events WHERE name = ?1;",
+        params![name],
+        |row| row.get(0),
+    );
+    match trashed {
+        Ok(true) => Error::Trashed(name.to_string()),
+        _ => Error::DuplicateName(name.to_string()),
     }
 }
 
-/// Insert test data into the data_base.
+/// Insert an occurrence of an event.
 ///
 /// ### Arguments
-/// - `&Connection` - The connection to the data_base.
+/// - conn: `&Connection` - The data_base connection.
+/// - id: `i64` - The id of the event.
+/// - event: `&str` - The name of the event, for error messages.
+/// - date: `NaiveDate` - The date of the occurrence.
+/// - details: `&OccurrenceDetails` - The details recorded with the occurrence.
 ///
 /// ### Returns
-/// - `()`
-pub fn insert_test_event(conn: &Connection) {
-    match conn.execute(
-        "INSERT INTO events (name) VALUES (?1), (?2);",
-        params!["Pooper empty", "Propane tank full"],
-    ) {
-        Ok(inserted) => {
-            info!("Record inserted: {}", inserted);
-            // Insert test occurrence.
-            match conn.execute(
-            "INSERT INTO occurrences (event_id, year, month, day) VALUES (?1, ?2, ?3, ?4), (?5, ?6, ?7, ?8), (?9, ?10, ?11, ?12), (?13, ?14, ?15, ?16);",
-            params![
-                1i32,
-                2023i32,
-                4i32,
-                1i32,
-                2i32,
-                2023i32,
-                4i32,
-                12i32,
-                1i32,
-                2023i32,
-                4i32,
-                6i32,
-                1i32,
-                2023i32,
-                4i32,
-                11i32,
-            ],
-        ) {
-            Ok(inserted) => info!("Record inserted: {}", inserted),
-            Err(e) => error!("Error inserting record: {}", e),
-        }
+/// - `Result<i64>` - The id of the occurrence, or `Error::Constraint` if the event already
+///   happened on that date.
+fn insert_occurrence(
+    conn: &Connection,
+    id: i64,
+    event: &str,
+    date: NaiveDate,
+    details: &OccurrenceDetails,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO occurrences (event_id, date, note, quantity, unit, cost, meter) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
+        params![
+            id,
+            date,
+            details.note,
+            details.quantity,
+            details.unit,
+            details.cost,
+            details.meter
+        ],
+    )
+    .map_err(|e| match Error::from(e) {
+        // The only constraint on a new occurrence is one per event per day.
+        Error::Constraint(_) => {
+            Error::Constraint(format!("\"{}\" already happened on {}.", event, date))
         }
-        Err(e) => error!("Error inserting record: {}", e),
+        e => e,
+    })?;
+    info!("Occurrence added: {} on {}", event, date);
+    Ok(conn.last_insert_rowid())
+}
+
+/// Replace the tags of an event, removing tags no event uses any more.
+///
+/// ### Arguments
+/// - conn: `&Connection` - The data_base connection.
+/// - id: `i64` - The id of the event.
+/// - tags: `&[String]` - The new tags.
+///
+/// ### Returns
+/// - `Result<()>`
+fn write_tags(conn: &Connection, id: i64, tags: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM event_tags WHERE event_id = ?1;", params![id])?;
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1);",
+            params![tag],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO event_tags (event_id, tag_id) \
+            SELECT ?1, id FROM tags WHERE name = ?2;",
+            params![id, tag],
+        )?;
     }
+    conn.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM event_tags);",
+        [],
+    )?;
+    Ok(())
 }
 
-/// Get events and occurrences from the data_base.
+/// Get the tags of every event.
 ///
 /// ### Arguments
-/// - conn - `&Connection` - The connection to the data_base.
+/// - conn: `&Connection` - The data_base connection.
 ///
 /// ### Returns
-/// - `Result<Vec<EventOccurrence>>` - The event occurrences.
-pub fn get_events(conn: &Connection) -> Result<Vec<EventOccurrence>> {
-    info!("Retrieving Records.");
-    // Get all events and occurrences.
+/// - `Result<HashMap<i64, Vec<String>>>` - `{event id: [tag,...]}`, tags sorted.
+fn tags_by_event(conn: &Connection) -> Result<HashMap<i64, Vec<String>>> {
     let mut stmt = prepare_stmt(
         conn,
         "\
-    SELECT name, year, month, day \
-    FROM events \
-    JOIN occurrences \
-    ON events.id = occurrences.event_id \
-    ORDER BY year, month, day DESC;",
-    );
-    let events = stmt
-        .query_map([], |row| {
-            Ok(EventOccurrence {
-                name: row.get(0)?,
-                year: row.get(1)?,
-                month: row.get(2)?,
-                day: row.get(3)?,
-            })
-        })?
-        .map(|event| {
-            event.unwrap_or_else(|e| {
-                error!("Error retrieving record: {}", e);
-                EventOccurrence {
-                    name: "".to_string(),
-                    year: 0,
-                    month: 0,
-                    day: 0,
-                }
-            })
-        })
-        .collect();
-    Ok(events)
+        SELECT et.event_id, t.name \
+        FROM event_tags et \
+        JOIN tags t \
+        ON t.id = et.tag_id \
+        ORDER BY t.name;",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))?;
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (id, tag) = row?;
+        tags.entry(id).or_default().push(tag);
+    }
+    Ok(tags)
 }
 
-/// Perform a SQL insert with variable parameters.
+/// Read an occurrence from a row with the columns of the `occurrences` table.
+///
+/// ### Arguments
+/// - row: `&Row` - The row.
+///
+/// ### Returns
+/// - `rusqlite::Result<Occurrence>`
+fn read_occurrence(row: &Row) -> rusqlite::Result<Occurrence> {
+    Ok(Occurrence {
+        id: row.get("id")?,
+        date: row.get("date")?,
+        details: OccurrenceDetails {
+            note: row.get("note")?,
+            quantity: row.get("quantity")?,
+            unit: row.get("unit")?,
+            cost: row.get("cost")?,
+            meter: row.get("meter")?,
+        },
+    })
+}
+
+/// Get an event and all of its occurrences.
 ///
 /// ### Arguments
 /// - conn: `&Connection` - The data_base connection.
-/// - id: `(i32, bool)` - The id of the event to insert.
-/// - date: `(&str, bool)` - The date of the occurrence to insert.
-/// - event: `(&str, bool)` - The name of the event to insert.
-///     - The bool portion of the tuple is a flag to determine if the parameter should be used.
-/// - sql: `&str` - The SQL statement to execute.
+/// - id: `i64` - The id of the event.
 ///
 /// ### Returns
-/// - `Result<i32, rusqlite::Error>` - bool success flag.
-pub fn sql_insert(
-    conn: &Connection,
-    id: (i32, bool),
-    date: (i32, u32, u32, bool),
-    event: (&str, bool),
-    sql: &str,
-) -> Result<i32, rusqlite::Error> {
-    let mut stmt = prepare_stmt(conn, sql);
-    // Match on the flags to determine which parameters to use.
-    match (id.1, date.3, event.1) {
-        // Update event with a new occurrence.
-        (true, true, false) => stmt
-            .execute(params![id.0, date.0, date.1, date.2])
-            .unwrap_or_else(|e| {
-                error!("Error: {:?}", e);
-                0
-            }),
-        // Add event/delete occurrence.
-        (false, false, true) => stmt.execute(params![event.0]).unwrap_or_else(|e| {
-            error!("Error: {:?}", e);
-            0
-        }),
-        // Delete event.
-        (true, false, false) => stmt.execute(params![id.0]).unwrap_or_else(|e| {
-            error!("Error: {:?}", e);
-            0
-        }),
-        _ => 0, // This should never happen.
-    };
-    Ok(id.0)
+/// - `Result<EventHistory>` - `Error::IdNotFound` if there is no such event.
+fn load_history(conn: &Connection, id: i64) -> Result<EventHistory> {
+    let (name, deleted_at, archived, meter, target_interval_days) = conn
+        .query_row(
+            "SELECT name, deleted_at, archived, meter_unit, meter_reading, target_interval_days \
+            FROM events WHERE id = ?1;",
+            params![id],
+            |row| {
+                let meter = match row.get::<_, Option<MeterUnit>>(3)? {
+                    Some(unit) => Some(Meter {
+                        unit,
+                        reading: row.get(4)?,
+                    }),
+                    None => None,
+                };
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, meter, row.get(5)?))
+            },
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::IdNotFound("event", id),
+            e => e.into(),
+        })?;
+    let mut stmt = prepare_stmt(
+        conn,
+        "SELECT * FROM occurrences WHERE event_id = ?1 ORDER BY date;",
+    )?;
+    let occurrences = stmt
+        .query_map(params![id], read_occurrence)?
+        .collect::<rusqlite::Result<Vec<Occurrence>>>()?;
+    let mut stmt = prepare_stmt(
+        conn,
+        "\
+        SELECT t.name \
+        FROM event_tags et \
+        JOIN tags t \
+        ON t.id = et.tag_id \
+        WHERE et.event_id = ?1 \
+        ORDER BY t.name;",
+    )?;
+    let tags = stmt
+        .query_map(params![id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(EventHistory {
+        id,
+        name,
+        occurrences,
+        deleted_at,
+        archived,
+        meter,
+        tags,
+        target_interval_days,
+    })
 }
 
-/// Get the id of the event.
+/// Delete an event and all of its occurrences for good.
 ///
 /// ### Arguments
 /// - conn: `&Connection` - The data_base connection.
-/// - event: `&str` - The name of the event.
+/// - id: `i64` - The id of the event.
 ///
 /// ### Returns
-/// - id: `i32` - The id of the event.
-pub fn get_event_id(conn: &Connection, event: &str) -> i32 {
-    struct ID {
-        id: i32,
+/// - `Result<EventHistory>` - The deleted event, or `Error::IdNotFound` if there is no such event.
+fn delete_history(conn: &Connection, id: i64) -> Result<EventHistory> {
+    let history = load_history(conn, id)?;
+    // Occurrences are removed by the cascading foreign key.
+    conn.execute("DELETE FROM events WHERE id = ?1;", params![id])?;
+    info!("Event purged: {}", history.name);
+    Ok(history)
+}
+
+/// Insert an event and all of its occurrences, keeping their ids.
+///
+/// ### Arguments
+/// - conn: `&Connection` - The data_base connection.
+/// - history: `&EventHistory` - The event, as returned by `load_history`.
+///
+/// ### Returns
+/// - `Result<()>` - `Error::DuplicateName` or `Error::Trashed` if the name is in use.
+fn insert_history(conn: &Connection, history: &EventHistory) -> Result<()> {
+    conn.execute(
+        "INSERT INTO events \
+        (id, name, deleted_at, archived, meter_unit, meter_reading, target_interval_days) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
+        params![
+            history.id,
+            history.name,
+            history.deleted_at,
+            history.archived,
+            history.meter.map(|meter| meter.unit),
+            history.meter.and_then(|meter| meter.reading),
+            history.target_interval_days
+        ],
+    )
+    .map_err(|e| match Error::from(e) {
+        Error::Constraint(_) => name_taken(conn, &history.name),
+        e => e,
+    })?;
+    for occurrence in &history.occurrences {
+        restore(conn, history.id, occurrence)?;
     }
-    info!("Getting event id for {:?}", event);
-    let mut id_stmt = prepare_stmt(conn, "SELECT id FROM events WHERE name = ?1;");
-    let ID { id } = id_stmt
-        .query_row(params![event], |row| Ok(ID { id: row.get(0)? }))
-        .unwrap_or_else(|e| {
-            error!("Error: {:?}", e);
-            ID { id: 0 }
-        });
-    id
+    write_tags(conn, history.id, &history.tags)
 }
 
-/// Add an event to the data_base.
+/// Move an event in or out of the trash.
 ///
 /// ### Arguments
-/// - event: `&str` - The name of the event to add.
-/// - date: `&str` - The date of the occurrence to add.
+/// - conn: `&Connection` - The data_base connection.
+/// - id: `i64` - The id of the event.
+/// - trashed: `bool` - `true` to move the event to the trash, `false` to restore it.
 ///
 /// ### Returns
-/// - `()`
-pub fn add_event(event: &str, year: i32, month: u32, day: u32) {
-    let conn = setup_connection();
-    match sql_insert(
-        &conn,
-        (0, false),
-        (year, month, day, false),
-        (event, true),
-        "INSERT INTO events (name) VALUES (?1);",
-    ) {
-        Ok(_) => {
-            info!("Event added: {:?}", event);
-            let id = get_event_id(&conn, event);
-            // Add the occurrence to the data_base.
-            match sql_insert(
-                &conn,
-                (id, true),
-                (year, month, day, true),
-                ("", false),
-                "INSERT INTO occurrences (event_id, year, month, day) VALUES (?1, ?2, ?3, ?4);",
-            ) {
-                Ok(_) => {
-                    info!("Occurrence added: {}, {}-{}-{}", event, year, month, day);
-                }
-                Err(e) => {
-                    error!("Error: {:?}", e);
-                }
-            };
-        }
-        // If the event already exists, do not add the occurrence.
-        Err(e) => {
-            error!("Error: {:?}", e);
+/// - `Result<()>` - `Error::IdNotFound` if there is no such event where it is being moved from.
+fn set_trashed(conn: &Connection, id: i64, trashed: bool) -> Result<()> {
+    let sql = if trashed {
+        "UPDATE events SET deleted_at = datetime('now') WHERE id = ?1 AND deleted_at IS NULL;"
+    } else {
+        "UPDATE events SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL;"
+    };
+    match conn.execute(sql, params![id])? {
+        0 => Err(Error::IdNotFound("event", id)),
+        _ => {
+            info!(
+                "Event {} {} the trash",
+                id,
+                if trashed { "moved to" } else { "restored from" }
+            );
+            Ok(())
         }
     }
 }
 
-/// Delete an event from the data_base.
+/// Insert an occurrence with a known id.
 ///
 /// ### Arguments
-/// - event: `&str` - The name of the event to delete.
+/// - conn: `&Connection` - The data_base connection.
+/// - event_id: `i64` - The id of the event.
+/// - occurrence: `&Occurrence` - The occurrence.
 ///
 /// ### Returns
-/// - `()`
-pub fn delete_event(event: &str) {
-    let conn = setup_connection();
-    let id = get_event_id(&conn, event);
-    // Delete occurrence.
-    match sql_insert(
-        &conn,
-        (id, true),
-        (0, 0, 0, false),
-        ("", false),
-        "DELETE FROM occurrences WHERE event_id = ?1;",
-    ) {
-        Ok(_) => {
-            info!("Occurrences deleted.");
-            // Delete event.
-            match sql_insert(
-                &conn,
-                (0, false),
-                (0, 0, 0, false),
-                (event, true),
-                "DELETE FROM events WHERE name = ?1;",
-            ) {
-                Ok(_) => {
-                    info!("Event deleted: {}", event);
-                }
+/// - `Result<()>` - `Error::Constraint` if the event already happened on that date.
+fn restore(conn: &Connection, event_id: i64, occurrence: &Occurrence) -> Result<()> {
+    conn.execute(
+        "INSERT INTO occurrences (id, event_id, date, note, quantity, unit, cost, meter) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);",
+        params![
+            occurrence.id,
+            event_id,
+            occurrence.date,
+            occurrence.details.note,
+            occurrence.details.quantity,
+            occurrence.details.unit,
+            occurrence.details.cost,
+            occurrence.details.meter
+        ],
+    )
+    .map_err(|e| match Error::from(e) {
+        Error::Constraint(_) => Error::Constraint(format!(
+            "The event already happened on {}.",
+            occurrence.date
+        )),
+        e => e,
+    })?;
+    Ok(())
+}
+
+/// The events data_base.
+///
+/// A `Store` owns a single connection for its lifetime, so open it once and share it.
+/// Clones share the same connection, so a clone can be moved onto another thread.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::database::{Error, Store};
+/// # use since_when_lib::utils::get_date;
+/// let store = Store::open_in_memory().unwrap();
+/// store.add_event("Oil change", get_date(2023, 4, 1)).unwrap();
+/// store.update_event("Oil change", get_date(2023, 7, 2)).unwrap();
+/// assert_eq!(store.get_events().unwrap().len(), 2);
+///
+/// // Typos are reported rather than recorded.
+/// assert_eq!(
+///     store.update_event("Oil chnage", get_date(2023, 8, 3)),
+///     Err(Error::NotFound("Oil chnage".to_string()))
+/// );
+/// assert_eq!(
+///     store.add_event("Oil change", get_date(2023, 8, 3)),
+///     Err(Error::DuplicateName("Oil change".to_string()))
+/// );
+///
+/// // Deleted events wait in the trash.
+/// let id = store.delete_event("Oil change").unwrap();
+/// assert!(store.get_events().unwrap().is_empty());
+/// store.restore_from_trash(id).unwrap();
+/// assert_eq!(store.get_events().unwrap().len(), 2);
+/// ```
+#[derive(Clone)]
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    /// Open the data_base file, creating and upgrading it as needed.
+    ///
+    /// ### Arguments
+    /// - path: `&Path` - The location of the data_base file.
+    ///
+    /// ### Returns
+    /// - `Result<Store>`
+    pub fn open(path: &Path) -> Result<Store> {
+        Self::from_connection(setup_connection(path)?)
+    }
+
+    /// Open an empty data_base that only lives in memory.
+    ///
+    /// ### Returns
+    /// - `Result<Store>`
+    pub fn open_in_memory() -> Result<Store> {
+        let conn = Connection::open_in_memory().map_err(|e| Error::Open(e.to_string()))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        Self::from_connection(conn)
+    }
+
+    /// Wrap an open connection, upgrading its schema.
+    ///
+    /// ### Arguments
+    /// - conn: `Connection` - The connection to the data_base.
+    ///
+    /// ### Returns
+    /// - `Result<Store>`
+    pub fn from_connection(conn: Connection) -> Result<Store> {
+        setup_tables(&conn)?;
+        Ok(Store {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// The underlying connection, for queries the `Store` does not provide.
+    ///
+    /// Other users of the `Store` wait until the returned guard is dropped.
+    ///
+    /// ### Returns
+    /// - `MutexGuard<'_, Connection>`
+    pub fn connection(&self) -> MutexGuard<'_, Connection> {
+        // A panic while holding the lock cannot leave the connection itself unusable.
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Insert test data into the data_base.
+    ///
+    /// ### Returns
+    /// - `Result<()>`
+    pub fn insert_test_event(&self) -> Result<()> {
+        let conn = self.connection();
+        let inserted = conn.execute(
+            "INSERT INTO events (name) VALUES (?1), (?2);",
+            params!["Pooper empty", "Propane tank full"],
+        )?;
+        info!("Record inserted: {}", inserted);
+        // Insert test occurrence.
+        let inserted = conn.execute(
+            "INSERT INTO occurrences (event_id, date) VALUES (?1, ?2), (?3, ?4), (?5, ?6), (?7, ?8);",
+            params![
+                1i32,
+                get_date(2023, 4, 1),
+                2i32,
+                get_date(2023, 4, 12),
+                1i32,
+                get_date(2023, 4, 6),
+                1i32,
+                get_date(2023, 4, 11),
+            ],
+        )?;
+        info!("Record inserted: {}", inserted);
+        Ok(())
+    }
+
+    /// Get events and occurrences from the data_base.
+    ///
+    /// ### Returns
+    /// - `Result<Vec<EventOccurrence>>` - The event occurrences, newest first.
+    pub fn get_events(&self) -> Result<Vec<EventOccurrence>> {
+        info!("Retrieving Records.");
+        let conn = self.connection();
+        // Get all events and occurrences.
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+        SELECT name, date, archived, cost, meter \
+        FROM events \
+        JOIN occurrences \
+        ON events.id = occurrences.event_id \
+        WHERE deleted_at IS NULL \
+        ORDER BY date DESC;",
+        )?;
+        let events = stmt
+            .query_map([], |row| {
+                Ok(EventOccurrence {
+                    name: row.get(0)?,
+                    date: row.get(1)?,
+                    archived: row.get(2)?,
+                    cost: row.get(3)?,
+                    meter: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<EventOccurrence>>>()?;
+        Ok(events)
+    }
+
+    /// Get the id of the event.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the event, or `Error::NotFound` if there is no such event
+    ///   or it is in the trash.
+    pub fn get_event_id(&self, event: &str) -> Result<i64> {
+        event_id(&self.connection(), event)
+    }
+
+    /// Add an event to the data_base.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event to add.
+    /// - date: `NaiveDate` - The date of the first occurrence.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the new event, `Error::DuplicateName` if the event
+    ///   already exists, or `Error::Trashed` if it is in the trash.
+    pub fn add_event(&self, event: &str, date: NaiveDate) -> Result<i64> {
+        self.add_event_with(event, date, &OccurrenceDetails::default())
+    }
+
+    /// Add an event to the data_base, with details like a note or cost for its first occurrence.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event to add.
+    /// - date: `NaiveDate` - The date of the first occurrence.
+    /// - details: `&OccurrenceDetails` - The details recorded with the first occurrence.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the new event, `Error::DuplicateName` if the event
+    ///   already exists, `Error::Trashed` if it is in the trash, or `Error::InvalidInput` if
+    ///   the name is blank. Whitespace around the name is dropped.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::{Error, Store};
+    /// # use since_when_lib::events::OccurrenceDetails;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// let details = OccurrenceDetails::default();
+    /// let id = store
+    ///     .add_event_with(" Haircut\n", get_date(2023, 4, 1), &details)
+    ///     .unwrap();
+    /// assert_eq!(store.get_event_id("Haircut"), Ok(id));
+    /// assert_eq!(
+    ///     store.add_event_with("  ", get_date(2023, 4, 1), &details),
+    ///     Err(Error::InvalidInput("An event needs a name.".to_string()))
+    /// );
+    /// ```
+    pub fn add_event_with(
+        &self,
+        event: &str,
+        date: NaiveDate,
+        details: &OccurrenceDetails,
+    ) -> Result<i64> {
+        self.add_tagged_event(event, date, details, &[])
+    }
+
+    /// Add an event to the data_base with its tags, all at once.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event to add.
+    /// - date: `NaiveDate` - The date of the first occurrence.
+    /// - details: `&OccurrenceDetails` - The details recorded with the first occurrence.
+    /// - tags: `&[String]` - The event's tags.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the new event, `Error::DuplicateName` if the event
+    ///   already exists, `Error::Trashed` if it is in the trash, or `Error::InvalidInput` if
+    ///   the name is blank.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::events::OccurrenceDetails;
+    /// # use since_when_lib::utils::{get_date, parse_tags};
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store
+    ///     .add_tagged_event(
+    ///         "Oil change",
+    ///         get_date(2023, 4, 1),
+    ///         &OccurrenceDetails::default(),
+    ///         &parse_tags("car"),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(store.event_history(id).unwrap().tags, ["car"]);
+    /// ```
+    pub fn add_tagged_event(
+        &self,
+        event: &str,
+        date: NaiveDate,
+        details: &OccurrenceDetails,
+        tags: &[String],
+    ) -> Result<i64> {
+        let event = event_name(event)?;
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("INSERT INTO events (name) VALUES (?1);", params![event])
+            .map_err(|e| match Error::from(e) {
+                // The only constraint on a new event is its unique name.
+                Error::Constraint(_) => name_taken(&tx, event),
+                e => e,
+            })?;
+        info!("Event added: {:?}", event);
+        let id = tx.last_insert_rowid();
+        // Add the occurrence to the data_base.
+        insert_occurrence(&tx, id, event, date, details)?;
+        if !tags.is_empty() {
+            write_tags(&tx, id, tags)?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Move an event and all of its occurrences to the trash.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event to delete.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the deleted event, to pass to `restore_from_trash`,
+    ///   or `Error::NotFound` if there is no such event.
+    pub fn delete_event(&self, event: &str) -> Result<i64> {
+        let conn = self.connection();
+        let id = event_id(&conn, event)?;
+        set_trashed(&conn, id, true)?;
+        Ok(id)
+    }
+
+    /// Move an event and all of its occurrences to the trash by id.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event to delete.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such event outside the trash.
+    pub fn trash_event(&self, id: i64) -> Result<()> {
+        set_trashed(&self.connection(), id, true)
+    }
+
+    /// Take an event out of the trash.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event to restore.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such event in the trash.
+    pub fn restore_from_trash(&self, id: i64) -> Result<()> {
+        set_trashed(&self.connection(), id, false)
+    }
+
+    /// Delete an event and all of its occurrences for good, whether or not it is in the trash.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event to purge.
+    ///
+    /// ### Returns
+    /// - `Result<EventHistory>` - The purged event, to pass to `restore_event`,
+    ///   or `Error::IdNotFound` if there is no such event.
+    pub fn purge_event(&self, id: i64) -> Result<EventHistory> {
+        delete_history(&self.connection(), id)
+    }
+
+    /// Get the events in the trash.
+    ///
+    /// ### Returns
+    /// - `Result<Vec<TrashEntry>>` - The trashed events, most recently deleted first.
+    pub fn trash(&self) -> Result<Vec<TrashEntry>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+            SELECT e.id, e.name, e.deleted_at, COUNT(o.id), MAX(o.date) \
+            FROM events e \
+            LEFT JOIN occurrences o \
+            ON e.id = o.event_id \
+            WHERE e.deleted_at IS NOT NULL \
+            GROUP BY e.id \
+            ORDER BY e.deleted_at DESC, e.name;",
+        )?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(TrashEntry {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    deleted_at: row.get(2)?,
+                    occurrences: row.get::<_, i64>(3)? as usize,
+                    last: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<TrashEntry>>>()?;
+        Ok(entries)
+    }
+
+    /// Purge events that have been in the trash longer than the retention period.
+    ///
+    /// ### Arguments
+    /// - retention_days: `u32` - How many days deleted events are kept.
+    ///
+    /// ### Returns
+    /// - `Result<usize>` - The number of events purged.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Haircut", get_date(2023, 4, 1)).unwrap();
+    /// store.delete_event("Haircut").unwrap();
+    /// assert_eq!(store.trash().unwrap()[0].name, "Haircut");
+    ///
+    /// // Deleted just now, so kept for a 30 day retention period.
+    /// assert_eq!(store.purge_trash(30).unwrap(), 0);
+    /// assert_eq!(store.purge_trash(0).unwrap(), 1);
+    /// assert!(store.trash().unwrap().is_empty());
+    /// ```
+    pub fn purge_trash(&self, retention_days: u32) -> Result<usize> {
+        // Occurrences are removed by the cascading foreign key.
+        let purged = self.connection().execute(
+            "DELETE FROM events WHERE deleted_at <= datetime('now', ?1);",
+            params![format!("-{} days", retention_days)],
+        )?;
+        info!("Purged {} events from the trash", purged);
+        Ok(purged)
+    }
+
+    /// Put a purged event back, with the same ids it had before.
+    ///
+    /// ### Arguments
+    /// - history: `&EventHistory` - The event returned when it was purged.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::DuplicateName` if another event has taken its name.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store.add_event("Haircut", get_date(2023, 4, 1)).unwrap();
+    /// store.update_event("Haircut", get_date(2023, 5, 1)).unwrap();
+    /// let before = store.event_history(id).unwrap();
+    ///
+    /// let purged = store.purge_event(id).unwrap();
+    /// store.restore_event(&purged).unwrap();
+    /// assert_eq!(store.event_history(id).unwrap(), before);
+    /// ```
+    pub fn restore_event(&self, history: &EventHistory) -> Result<()> {
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        insert_history(&tx, history)?;
+        tx.commit()?;
+        info!("Event restored: {}", history.name);
+        Ok(())
+    }
+
+    /// Add an occurrence of an existing event to the data_base.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event to update.
+    /// - date: `NaiveDate` - The date of the occurrence.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the new occurrence, or `Error::NotFound` if there is no such event.
+    pub fn update_event(&self, event: &str, date: NaiveDate) -> Result<i64> {
+        self.update_event_with(event, date, &OccurrenceDetails::default())
+    }
+
+    /// Add an occurrence of an existing event, with details like a note, to the data_base.
+    ///
+    /// ### Arguments
+    /// - event: `&str` - The name of the event to update.
+    /// - date: `NaiveDate` - The date of the occurrence.
+    /// - details: `&OccurrenceDetails` - The details recorded with the occurrence.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the new occurrence, or `Error::NotFound` if there is no such event.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::events::OccurrenceDetails;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store.add_event("Oil change", get_date(2023, 4, 1)).unwrap();
+    /// let details = OccurrenceDetails {
+    ///     note: Some("Used synthetic 5W-30".to_string()),
+    ///     quantity: Some(5.0),
+    ///     unit: Some("qt".to_string()),
+    ///     cost: Some(45.5),
+    ///     meter: Some(38_200.0),
+    /// };
+    /// store
+    ///     .update_event_with("Oil change", get_date(2023, 7, 2), &details)
+    ///     .unwrap();
+    ///
+    /// let history = store.event_history(id).unwrap();
+    /// assert_eq!(history.occurrences[0].details.note, None);
+    /// assert_eq!(history.occurrences[1].details, details);
+    /// let july = store.events_by_year_month(2023, 7).unwrap();
+    /// assert_eq!(july[&2][0].note.as_deref(), Some("Used synthetic 5W-30"));
+    ///
+    /// // Names are matched without the whitespace around them, as they were added.
+    /// store
+    ///     .update_event_with(" Oil change\n", get_date(2023, 10, 3), &details)
+    ///     .unwrap();
+    /// assert_eq!(store.event_history(id).unwrap().occurrences.len(), 3);
+    /// ```
+    pub fn update_event_with(
+        &self,
+        event: &str,
+        date: NaiveDate,
+        details: &OccurrenceDetails,
+    ) -> Result<i64> {
+        let conn = self.connection();
+        let id = event_id(&conn, event)?;
+        insert_occurrence(&conn, id, event.trim(), date, details)
+    }
+
+    /// Get every occurrence of the events outside the trash.
+    ///
+    /// ### Returns
+    /// - `Result<Vec<(String, Occurrence)>>` - `(event, occurrence)`, by event name and then
+    ///   oldest first.
+    pub fn all_occurrences(&self) -> Result<Vec<(String, Occurrence)>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+            SELECT e.name, o.* \
+            FROM events e \
+            JOIN occurrences o \
+            ON e.id = o.event_id \
+            WHERE e.deleted_at IS NULL \
+            ORDER BY e.name, o.date;",
+        )?;
+        let occurrences = stmt
+            .query_map([], |row| Ok((row.get(0)?, read_occurrence(row)?)))?
+            .collect::<rusqlite::Result<Vec<(String, Occurrence)>>>()?;
+        Ok(occurrences)
+    }
+
+    /// Add occurrences read from a file, creating events that do not exist yet.
+    ///
+    /// Occurrences on a day their event already has are skipped. Rows that cannot be added,
+    /// like those of an event in the trash, are reported rather than stopping the import.
+    ///
+    /// ### Arguments
+    /// - rows: `&[ImportRow]` - The occurrences to add.
+    /// - dry_run: `bool` - `true` to report what would change without saving anything.
+    ///
+    /// ### Returns
+    /// - `Result<ImportReport>` - What was, or would be, added.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::events::OccurrenceDetails;
+    /// # use since_when_lib::transfer::ImportRow;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Oil change", get_date(2023, 4, 1)).unwrap();
+    /// let row = |line, event: &str, day| ImportRow {
+    ///     line,
+    ///     event: event.to_string(),
+    ///     date: get_date(2023, 4, day),
+    ///     details: OccurrenceDetails::default(),
+    /// };
+    /// let rows = [row(2, "Oil change", 1), row(3, "Oil change", 2), row(4, "Haircut", 3)];
+    ///
+    /// let report = store.import_occurrences(&rows, true).unwrap();
+    /// assert_eq!((report.added, report.skipped), (2, 1));
+    /// assert_eq!(report.created, ["Haircut"]);
+    /// assert!(store.get_event_id("Haircut").is_err());
+    ///
+    /// store.import_occurrences(&rows, false).unwrap();
+    /// assert!(store.get_event_id("Haircut").is_ok());
+    /// assert_eq!(store.get_events().unwrap().len(), 3);
+    /// ```
+    pub fn import_occurrences(&self, rows: &[ImportRow], dry_run: bool) -> Result<ImportReport> {
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        for row in rows {
+            let event = match event_name(&row.event) {
+                Ok(event) => event,
                 Err(e) => {
-                    error!("Error: {:?}", e);
+                    report.errors.push(RowError {
+                        line: row.line,
+                        message: e.to_string(),
+                    });
+                    continue;
                 }
+            };
+            let id = match event_id(&tx, event) {
+                Ok(id) => id,
+                Err(Error::NotFound(_)) => {
+                    let inserted =
+                        tx.execute("INSERT INTO events (name) VALUES (?1);", params![event]);
+                    match inserted.map_err(Error::from) {
+                        Ok(_) => {
+                            info!("Event imported: {:?}", event);
+                            report.created.push(event.to_string());
+                            tx.last_insert_rowid()
+                        }
+                        // The only constraint on a new event is its unique name.
+                        Err(Error::Constraint(_)) => {
+                            report.errors.push(RowError {
+                                line: row.line,
+                                message: name_taken(&tx, event).to_string(),
+                            });
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            };
+            match insert_occurrence(&tx, id, event, row.date, &row.details) {
+                Ok(_) => report.added += 1,
+                Err(Error::Constraint(_)) => report.skipped += 1,
+                Err(e) => return Err(e),
             }
-            id
         }
-        Err(e) => {
-            error!("Error: {:?}", e);
-            0
+        // Dropping the transaction rolls it back.
+        if !dry_run {
+            tx.commit()?;
         }
-    };
-}
+        Ok(report)
+    }
 
-/// Update an event in the data_base.
-///
-/// ### Arguments
-/// - event: `&str` - The name of the event to update.
-/// - date: `&str` - The date of the occurrence to update.
-///
-/// ### Returns
-/// - `()`
-pub fn update_event(event: &str, year: i32, month: u32, day: u32) {
-    let conn = setup_connection();
-    let id = get_event_id(&conn, event);
-    // Add the occurrence to the data_base.
-    match sql_insert(
-        &conn,
-        (id, true),
-        (year, month, day, true),
-        ("", false),
-        "INSERT INTO occurrences (event_id, year, month, day) VALUES (?1, ?2, ?3, ?4);",
-    ) {
-        Ok(_) => {
-            info!("Occurrence added: {} on {}-{}-{}", event, year, month, day);
+    /// Archive an event, hiding it from the calendar and, unless asked for, the events page,
+    /// or take it out of the archive.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event.
+    /// - archived: `bool` - `true` to archive the event, `false` to unarchive it.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such event.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store.add_event("Since we moved house", get_date(2023, 4, 1)).unwrap();
+    ///
+    /// store.set_archived(id, true).unwrap();
+    /// assert!(store.event_history(id).unwrap().archived);
+    /// assert!(store.events_by_year_month(2023, 4).unwrap().is_empty());
+    ///
+    /// store.set_archived(id, false).unwrap();
+    /// assert_eq!(store.events_by_year_month(2023, 4).unwrap().len(), 1);
+    /// ```
+    pub fn set_archived(&self, id: i64, archived: bool) -> Result<()> {
+        let changed = self.connection().execute(
+            "UPDATE events SET archived = ?2 WHERE id = ?1;",
+            params![id, archived],
+        )?;
+        match changed {
+            0 => Err(Error::IdNotFound("event", id)),
+            _ => {
+                info!("Event {} archived: {}", id, archived);
+                Ok(())
+            }
         }
-        Err(e) => {
-            error!("Error: {:?}", e);
+    }
+
+    /// Give an event a meter, like an odometer, update its latest reading, or remove it.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event.
+    /// - meter: `Option<Meter>` - The meter, or `None` to remove it. Readings already recorded
+    ///   with occurrences are kept.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such event.
+    pub fn set_meter(&self, id: i64, meter: Option<Meter>) -> Result<()> {
+        let changed = self.connection().execute(
+            "UPDATE events SET meter_unit = ?2, meter_reading = ?3 WHERE id = ?1;",
+            params![
+                id,
+                meter.map(|meter| meter.unit),
+                meter.and_then(|meter| meter.reading)
+            ],
+        )?;
+        match changed {
+            0 => Err(Error::IdNotFound("event", id)),
+            _ => {
+                info!("Event {} meter set to {:?}", id, meter);
+                Ok(())
+            }
         }
-    };
-}
+    }
 
-/// Get events by year and month.
-///
-/// ### Returns
-/// - `Result<HashMap<i32, Vec<String>>>` `{day: [event,...]}`
-pub fn events_by_year_month(year: i32, month: u32) -> Result<HashMap<u32, Vec<String>>> {
-    let conn = setup_connection();
-    let mut stmt = prepare_stmt(
-        &conn,
-        "\
-        SELECT e.name, o.day \
-        FROM events e \
-        JOIN occurrences o \
-        ON e.id = o.event_id \
-        WHERE o.year = ? and o.month = ?;",
-    );
-    struct EventDay {
-        name: String,
-        day: u32,
-    }
-    let event_iter = stmt.query_map(params![year, month as i32], |row| {
-        Ok(EventDay {
-            name: row.get(0)?,
-            day: row.get(1)?,
+    /// Get the meters of the events outside the trash.
+    ///
+    /// ### Returns
+    /// - `Result<HashMap<String, Meter>>` - `{event: meter}` for each event with a meter.
+    pub fn meters(&self) -> Result<HashMap<String, Meter>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+            SELECT name, meter_unit, meter_reading \
+            FROM events \
+            WHERE meter_unit IS NOT NULL \
+            AND deleted_at IS NULL;",
+        )?;
+        let meters = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    Meter {
+                        unit: row.get(1)?,
+                        reading: row.get(2)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<HashMap<String, Meter>>>()?;
+        Ok(meters)
+    }
+
+    /// Set how often an event is meant to happen, or clear it.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event.
+    /// - days: `Option<u32>` - The target interval in days, or `None` to clear it.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such event.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store.add_event("Replace furnace filter", get_date(2023, 4, 1)).unwrap();
+    ///
+    /// store.set_target_interval(id, Some(90)).unwrap();
+    /// assert_eq!(store.event_history(id).unwrap().target_interval_days, Some(90));
+    /// assert_eq!(store.target_intervals().unwrap()["Replace furnace filter"], 90);
+    ///
+    /// store.set_target_interval(id, None).unwrap();
+    /// assert!(store.target_intervals().unwrap().is_empty());
+    /// ```
+    pub fn set_target_interval(&self, id: i64, days: Option<u32>) -> Result<()> {
+        let changed = self.connection().execute(
+            "UPDATE events SET target_interval_days = ?2 WHERE id = ?1;",
+            params![id, days],
+        )?;
+        match changed {
+            0 => Err(Error::IdNotFound("event", id)),
+            _ => {
+                info!("Event {} target interval set to {:?} days", id, days);
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the ids of the events outside the trash.
+    ///
+    /// ### Returns
+    /// - `Result<HashMap<String, i64>>` - `{event: id}`.
+    pub fn event_ids(&self) -> Result<HashMap<String, i64>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "SELECT name, id FROM events WHERE deleted_at IS NULL;",
+        )?;
+        let ids = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<HashMap<String, i64>>>()?;
+        Ok(ids)
+    }
+
+    /// Get how often the events outside the trash are meant to happen.
+    ///
+    /// ### Returns
+    /// - `Result<HashMap<String, u32>>` - `{event: days}` for each event with a target interval.
+    pub fn target_intervals(&self) -> Result<HashMap<String, u32>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+            SELECT name, target_interval_days \
+            FROM events \
+            WHERE target_interval_days IS NOT NULL \
+            AND deleted_at IS NULL;",
+        )?;
+        let targets = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<HashMap<String, u32>>>()?;
+        Ok(targets)
+    }
+
+    /// Replace the tags of an event.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event.
+    /// - tags: `&[String]` - The new tags, as returned by `utils::parse_tags`.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such event.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::utils::{get_date, parse_tags};
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store.add_event("Oil change", get_date(2023, 4, 1)).unwrap();
+    /// store.add_event("Haircut", get_date(2023, 4, 2)).unwrap();
+    ///
+    /// store.set_tags(id, &parse_tags("Car, maintenance")).unwrap();
+    /// assert_eq!(store.event_history(id).unwrap().tags, ["car", "maintenance"]);
+    /// assert_eq!(store.tags().unwrap(), ["car", "maintenance"]);
+    /// assert!(store.event_tags().unwrap().get("Haircut").is_none());
+    ///
+    /// // Tags no event uses are removed.
+    /// store.set_tags(id, &parse_tags("car")).unwrap();
+    /// assert_eq!(store.tags().unwrap(), ["car"]);
+    /// ```
+    pub fn set_tags(&self, id: i64, tags: &[String]) -> Result<()> {
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        tx.query_row("SELECT id FROM events WHERE id = ?1;", params![id], |_| {
+            Ok(())
         })
-    })?;
-    let mut events_by_year_month: HashMap<u32, Vec<String>> = HashMap::new();
-    for event_result in event_iter {
-        let event = event_result.unwrap_or_else(|e| {
-            error!("Error getting record {}", e);
-            EventDay {
-                name: "".to_string(),
-                day: 0,
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Error::IdNotFound("event", id),
+            e => e.into(),
+        })?;
+        write_tags(&tx, id, tags)?;
+        tx.commit()?;
+        info!("Event {} tagged: {:?}", id, tags);
+        Ok(())
+    }
+
+    /// Get every tag used by an event outside the trash.
+    ///
+    /// ### Returns
+    /// - `Result<Vec<String>>` - The tags, sorted.
+    pub fn tags(&self) -> Result<Vec<String>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+            SELECT DISTINCT t.name \
+            FROM tags t \
+            JOIN event_tags et \
+            ON t.id = et.tag_id \
+            JOIN events e \
+            ON e.id = et.event_id \
+            WHERE e.deleted_at IS NULL \
+            ORDER BY t.name;",
+        )?;
+        let tags = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(tags)
+    }
+
+    /// Get the tags of the events outside the trash.
+    ///
+    /// ### Returns
+    /// - `Result<HashMap<String, Vec<String>>>` - `{event: [tag,...]}` for each tagged event,
+    ///   tags sorted.
+    pub fn event_tags(&self) -> Result<HashMap<String, Vec<String>>> {
+        let conn = self.connection();
+        let mut tags = tags_by_event(&conn)?;
+        let mut stmt = prepare_stmt(
+            &conn,
+            "SELECT id, name FROM events WHERE deleted_at IS NULL;",
+        )?;
+        let names = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut event_tags = HashMap::new();
+        for name in names {
+            let (id, name) = name?;
+            if let Some(tags) = tags.remove(&id) {
+                event_tags.insert(name, tags);
             }
-        });
-        match events_by_year_month.get_mut(&event.day) {
-            Some(event_vec) => event_vec.push(event.name),
-            None => {
-                events_by_year_month.insert(event.day.clone(), vec![event.name.clone()]);
+        }
+        Ok(event_tags)
+    }
+
+    /// Get events by year and month.
+    ///
+    /// Archived events are left out.
+    ///
+    /// ### Arguments
+    /// - year: `i32`
+    /// - month: `u32`
+    ///
+    /// ### Returns
+    /// - `Result<HashMap<u32, Vec<DayEvent>>>` `{day: [event,...]}`
+    pub fn events_by_year_month(
+        &self,
+        year: i32,
+        month: u32,
+    ) -> Result<HashMap<u32, Vec<DayEvent>>> {
+        let conn = self.connection();
+        let mut stmt = prepare_stmt(
+            &conn,
+            "\
+            SELECT e.name, o.date, o.note, e.id \
+            FROM events e \
+            JOIN occurrences o \
+            ON e.id = o.event_id \
+            WHERE o.date BETWEEN ?1 AND ?2 \
+            AND e.deleted_at IS NULL \
+            AND NOT e.archived;",
+        )?;
+        let first = get_date(year, month, 1);
+        let last = get_date(year, month, last_day_of_month(year, month) as u32);
+        let tags = tags_by_event(&conn)?;
+        let event_iter = stmt.query_map(params![first, last], |row| {
+            Ok((
+                row.get::<_, NaiveDate>(1)?,
+                DayEvent {
+                    name: row.get(0)?,
+                    note: row.get(2)?,
+                    tags: tags.get(&row.get(3)?).cloned().unwrap_or_default(),
+                },
+            ))
+        })?;
+        let mut events_by_year_month: HashMap<u32, Vec<DayEvent>> = HashMap::new();
+        for event_result in event_iter {
+            let (date, event) = event_result?;
+            events_by_year_month
+                .entry(date.day())
+                .or_default()
+                .push(event);
+        }
+        Ok(events_by_year_month)
+    }
+
+    /// Get an event and all of its occurrences.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the event.
+    ///
+    /// ### Returns
+    /// - `Result<EventHistory>` - `Error::IdNotFound` if there is no such event.
+    pub fn event_history(&self, id: i64) -> Result<EventHistory> {
+        load_history(&self.connection(), id)
+    }
+
+    /// Move an occurrence to another date.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the occurrence.
+    /// - date: `NaiveDate` - The new date.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::IdNotFound` if there is no such occurrence, or
+    ///   `Error::Constraint` if the event already happened on that date.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Haircut", get_date(2023, 4, 1)).unwrap();
+    /// store.update_event("Haircut", get_date(2023, 5, 1)).unwrap();
+    /// let id = store.get_event_id("Haircut").unwrap();
+    /// let first = store.event_history(id).unwrap().occurrences[0].id;
+    ///
+    /// store.move_occurrence(first, get_date(2023, 4, 2)).unwrap();
+    /// // Only one occurrence per day.
+    /// assert!(store.move_occurrence(first, get_date(2023, 5, 1)).is_err());
+    /// store.delete_occurrence(first).unwrap();
+    ///
+    /// let history = store.event_history(id).unwrap();
+    /// assert_eq!(history.occurrences.len(), 1);
+    /// assert_eq!(history.occurrences[0].date, get_date(2023, 5, 1));
+    /// ```
+    pub fn move_occurrence(&self, id: i64, date: NaiveDate) -> Result<()> {
+        let changed = self
+            .connection()
+            .execute(
+                "UPDATE occurrences SET date = ?2 WHERE id = ?1;",
+                params![id, date],
+            )
+            .map_err(|e| match Error::from(e) {
+                Error::Constraint(_) => {
+                    Error::Constraint(format!("The event already happened on {}.", date))
+                }
+                e => e,
+            })?;
+        match changed {
+            0 => Err(Error::IdNotFound("occurrence", id)),
+            _ => {
+                info!("Occurrence {} moved to {}", id, date);
+                Ok(())
             }
-        };
+        }
+    }
+
+    /// Delete a single occurrence.
+    ///
+    /// An event's only occurrence is kept, since an event without occurrences is shown
+    /// nowhere; delete the event instead.
+    ///
+    /// ### Arguments
+    /// - id: `i64` - The id of the occurrence.
+    ///
+    /// ### Returns
+    /// - `Result<(i64, Occurrence)>` - The event id and the deleted occurrence, to pass to
+    ///   `restore_occurrence`, `Error::IdNotFound` if there is no such occurrence, or
+    ///   `Error::InvalidInput` if it is the event's only one.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::{Error, Store};
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// let id = store.add_event("Haircut", get_date(2023, 4, 1)).unwrap();
+    /// let first = store.event_history(id).unwrap().occurrences[0].id;
+    ///
+    /// assert!(matches!(store.delete_occurrence(first), Err(Error::InvalidInput(_))));
+    /// assert_eq!(store.event_history(id).unwrap().occurrences.len(), 1);
+    ///
+    /// store.update_event("Haircut", get_date(2023, 5, 1)).unwrap();
+    /// let (event_id, occurrence) = store.delete_occurrence(first).unwrap();
+    /// assert_eq!((event_id, occurrence.date), (id, get_date(2023, 4, 1)));
+    /// ```
+    pub fn delete_occurrence(&self, id: i64) -> Result<(i64, Occurrence)> {
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        let (event_id, occurrence) = tx
+            .query_row(
+                "SELECT * FROM occurrences WHERE id = ?1;",
+                params![id],
+                |row| Ok((row.get("event_id")?, read_occurrence(row)?)),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Error::IdNotFound("occurrence", id),
+                e => e.into(),
+            })?;
+        let count: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM occurrences WHERE event_id = ?1;",
+            params![event_id],
+            |row| row.get(0),
+        )?;
+        if count == 1 {
+            return Err(Error::InvalidInput(
+                "This is the event's only occurrence. Delete the event instead.".to_string(),
+            ));
+        }
+        tx.execute("DELETE FROM occurrences WHERE id = ?1;", params![id])?;
+        tx.commit()?;
+        info!("Occurrence {} deleted", id);
+        Ok((event_id, occurrence))
+    }
+
+    /// Put a deleted occurrence back, with the same id it had before.
+    ///
+    /// ### Arguments
+    /// - event_id: `i64` - The id of the event.
+    /// - occurrence: `&Occurrence` - The occurrence returned when it was deleted.
+    ///
+    /// ### Returns
+    /// - `Result<()>`
+    pub fn restore_occurrence(&self, event_id: i64, occurrence: &Occurrence) -> Result<()> {
+        restore(&self.connection(), event_id, occurrence)
+    }
+
+    /// Rename an event, keeping its occurrences.
+    ///
+    /// ### Arguments
+    /// - old: `&str` - The current name of the event.
+    /// - new: `&str` - The new name.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::NotFound` if there is no event named `old`,
+    ///   `Error::DuplicateName` if an event named `new` already exists, or
+    ///   `Error::Trashed` if it is in the trash.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::{Error, Store};
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Oil chnage", get_date(2023, 4, 1)).unwrap();
+    /// store.add_event("Oil change", get_date(2023, 7, 2)).unwrap();
+    ///
+    /// assert_eq!(
+    ///     store.rename_event("Oil chnage", "Oil change"),
+    ///     Err(Error::DuplicateName("Oil change".to_string()))
+    /// );
+    /// // Merge instead, keeping both occurrences.
+    /// let id = store.merge_events(&["Oil chnage"], "Oil change").unwrap().target.id;
+    /// assert_eq!(store.event_history(id).unwrap().occurrences.len(), 2);
+    ///
+    /// store.rename_event("Oil change", "Changed oil").unwrap();
+    /// assert_eq!(store.get_event_id("Changed oil"), Ok(id));
+    /// ```
+    pub fn rename_event(&self, old: &str, new: &str) -> Result<()> {
+        let old = old.trim();
+        let new = event_name(new)?;
+        let conn = self.connection();
+        let changed = conn
+            .execute(
+                "UPDATE events SET name = ?2 WHERE name = ?1 AND deleted_at IS NULL;",
+                params![old, new],
+            )
+            .map_err(|e| match Error::from(e) {
+                // The only constraint on a name is that it is unique.
+                Error::Constraint(_) => name_taken(&conn, new),
+                e => e,
+            })?;
+        match changed {
+            0 => Err(Error::NotFound(old.to_string())),
+            _ => {
+                info!("Event renamed: {} -> {}", old, new);
+                Ok(())
+            }
+        }
+    }
+
+    /// Merge events into one, moving all of their occurrences to the target event
+    /// and deleting the source events.
+    ///
+    /// Occurrences on a day the target already has are dropped, after filling in any details
+    /// the target's occurrence is missing, like its note or cost.
+    ///
+    /// ### Arguments
+    /// - sources: `&[&str]` - The names of the events to merge away.
+    /// - target: `&str` - The name of the event to keep.
+    ///
+    /// ### Returns
+    /// - `Result<Merge>` - The events before and after the merge, for undoing it with
+    ///   `unmerge_events`, or `Error::NotFound` if any event is missing.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::events::OccurrenceDetails;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Oil change", get_date(2023, 4, 1)).unwrap();
+    /// store.add_event("Oil Change", get_date(2023, 7, 2)).unwrap();
+    /// let details = OccurrenceDetails {
+    ///     note: Some("5W-30".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// store
+    ///     .add_event_with("Changed oil", get_date(2023, 7, 2), &details)
+    ///     .unwrap();
+    /// store.update_event("Changed oil", get_date(2023, 10, 3)).unwrap();
+    ///
+    /// let merge = store
+    ///     .merge_events(&["Oil Change", "Changed oil"], "Oil change")
+    ///     .unwrap();
+    /// // The two occurrences on July 2nd become one, keeping the note.
+    /// let history = store.event_history(merge.target.id).unwrap();
+    /// let dates: Vec<_> = history
+    ///     .occurrences
+    ///     .iter()
+    ///     .map(|occurrence| occurrence.date)
+    ///     .collect();
+    /// assert_eq!(
+    ///     dates,
+    ///     [get_date(2023, 4, 1), get_date(2023, 7, 2), get_date(2023, 10, 3)]
+    /// );
+    /// assert_eq!(history.occurrences[1].details, details);
+    /// assert!(store.get_event_id("Changed oil").is_err());
+    ///
+    /// // Undo puts every event back as it was.
+    /// store.unmerge_events(&merge).unwrap();
+    /// assert_eq!(store.event_history(merge.target.id).unwrap(), merge.target);
+    /// assert_eq!(store.event_history(merge.sources[1].id).unwrap(), merge.sources[1]);
+    ///
+    /// // Unless the event was changed since, which undoing would lose.
+    /// let merge = store.merge_events(&["Oil Change"], "Oil change").unwrap();
+    /// store.update_event("Oil change", get_date(2024, 1, 5)).unwrap();
+    /// assert!(store.unmerge_events(&merge).is_err());
+    /// assert_eq!(store.event_history(merge.target.id).unwrap().occurrences.len(), 3);
+    /// ```
+    pub fn merge_events(&self, sources: &[&str], target: &str) -> Result<Merge> {
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        let target_id = event_id(&tx, target)?;
+        let before = load_history(&tx, target_id)?;
+        let mut merged_sources = Vec::new();
+        for source in sources {
+            let source_id = event_id(&tx, source)?;
+            if source_id == target_id {
+                return Err(Error::InvalidInput(
+                    "An event cannot be merged into itself.".to_string(),
+                ));
+            }
+            merged_sources.push(load_history(&tx, source_id)?);
+            tx.execute(
+                "UPDATE OR IGNORE occurrences SET event_id = ?2 WHERE event_id = ?1;",
+                params![source_id, target_id],
+            )?;
+            // Same day occurrences stay behind, so fill in what the target's are missing
+            // before they are removed with the source event.
+            tx.execute(
+                "\
+                UPDATE occurrences AS kept SET \
+                note = COALESCE(kept.note, dropped.note), \
+                quantity = COALESCE(kept.quantity, dropped.quantity), \
+                unit = COALESCE(kept.unit, dropped.unit), \
+                cost = COALESCE(kept.cost, dropped.cost), \
+                meter = COALESCE(kept.meter, dropped.meter) \
+                FROM occurrences AS dropped \
+                WHERE dropped.event_id = ?1 AND kept.event_id = ?2 AND kept.date = dropped.date;",
+                params![source_id, target_id],
+            )?;
+            // The target keeps the tags of every merged event.
+            tx.execute(
+                "INSERT OR IGNORE INTO event_tags (event_id, tag_id) \
+                SELECT ?2, tag_id FROM event_tags WHERE event_id = ?1;",
+                params![source_id, target_id],
+            )?;
+            tx.execute("DELETE FROM events WHERE id = ?1;", params![source_id])?;
+            info!("Event merged: {} -> {}", source, target);
+        }
+        let merged = load_history(&tx, target_id)?;
+        tx.commit()?;
+        Ok(Merge {
+            target: before,
+            sources: merged_sources,
+            merged,
+        })
+    }
+
+    /// Undo a merge, putting the target event and the merged events back as they were.
+    ///
+    /// ### Arguments
+    /// - merge: `&Merge` - The merge, as returned by `merge_events`.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - `Error::InvalidInput` if the target event changed since the merge,
+    ///   since undoing it would lose those changes, `Error::IdNotFound` if the target event is
+    ///   gone, or `Error::DuplicateName` if a merged event's name was taken since.
+    pub fn unmerge_events(&self, merge: &Merge) -> Result<()> {
+        let conn = self.connection();
+        let tx = conn.unchecked_transaction()?;
+        if delete_history(&tx, merge.target.id)? != merge.merged {
+            // Dropping the transaction puts the event back.
+            return Err(Error::InvalidInput(format!(
+                "\"{}\" changed since the merge, so the merge cannot be undone.",
+                merge.merged.name
+            )));
+        }
+        insert_history(&tx, &merge.target)?;
+        for source in &merge.sources {
+            insert_history(&tx, source)?;
+        }
+        tx.commit()?;
+        info!("Merge into {} undone", merge.target.name);
+        Ok(())
     }
-    Ok(events_by_year_month)
 }
@@ -0,0 +1,531 @@
+use chrono::NaiveDate;
+use iced::alignment::Horizontal;
+use iced::widget::{pick_list, row, text, text_input, Column, Text};
+use iced::{Alignment, Command};
+
+use crate::{
+    app::AppMessage,
+    database::{Error, Result, Store},
+    events::{EventHistory, Meter, MeterUnit},
+    settings::Settings,
+    undo::Change,
+    utils::{
+        background, format_number, get_meter_summary, mutate, new_button, parse_amount, parse_tags,
+        record,
+    },
+};
+
+/// EventDetail state.
+///
+/// Holds the history of one event, the occurrence being moved, if any,
+/// the event's new name while renaming, and its meter, tags and target interval while editing
+/// them.
+#[derive(Debug, Clone)]
+pub struct EventDetail {
+    history: Option<EventHistory>,
+    editing: Option<i64>,
+    date: String,
+    name: String,
+    merge_target: Option<String>,
+    meter_unit: Option<MeterUnit>,
+    reading: String,
+    tags: String,
+    target: String,
+    loading: bool,
+}
+
+/// Default EventDetail implementation.
+impl Default for EventDetail {
+    fn default() -> Self {
+        EventDetail::new()
+    }
+}
+
+/// EventDetail implementation.
+impl<'a> EventDetail {
+    pub fn new() -> EventDetail {
+        Self {
+            history: None,
+            editing: None,
+            date: String::new(),
+            name: String::new(),
+            merge_target: None,
+            meter_unit: None,
+            reading: String::new(),
+            tags: String::new(),
+            target: String::new(),
+            loading: false,
+        }
+    }
+
+    /// Load the history of an event in the background.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    /// - id: `i64` - The id of the event.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::EventLoaded` when done.
+    pub fn load(&mut self, store: &Store, id: i64) -> Command<AppMessage> {
+        self.loading = true;
+        let store = store.clone();
+        background(move || store.event_history(id), AppMessage::EventLoaded)
+    }
+
+    /// Load the history of an event by name in the background.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    /// - event: `String` - The name of the event.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::EventLoaded` when done.
+    pub fn load_by_name(&mut self, store: &Store, event: String) -> Command<AppMessage> {
+        self.loading = true;
+        let store = store.clone();
+        background(
+            move || store.event_history(store.get_event_id(&event)?),
+            AppMessage::EventLoaded,
+        )
+    }
+
+    /// Show a freshly loaded event history.
+    ///
+    /// ### Arguments
+    /// - history: `Result<EventHistory>` - The result of `load`.
+    ///
+    /// ### Returns
+    /// - `Result<i64>` - The id of the event, or the error from loading.
+    pub fn loaded(&mut self, history: Result<EventHistory>) -> Result<i64> {
+        self.loading = false;
+        let history = history?;
+        let id = history.id;
+        // Stop editing if the occurrence is gone.
+        if !history
+            .occurrences
+            .iter()
+            .any(|occurrence| Some(occurrence.id) == self.editing)
+        {
+            self.editing = None;
+        }
+        // Start renaming and editing the meter, tags and target from the current values when
+        // showing a different event.
+        if self.history.as_ref().map(|history| history.id) != Some(id) {
+            self.name = history.name.clone();
+            self.merge_target = None;
+            self.meter_unit = history.meter.map(|meter| meter.unit);
+            self.reading = history
+                .meter
+                .and_then(|meter| meter.reading)
+                .map(format_number)
+                .unwrap_or_default();
+            self.tags = history.tags.join(", ");
+            self.target = history
+                .target_interval_days
+                .map(|days| days.to_string())
+                .unwrap_or_default();
+        }
+        self.history = Some(history);
+        Ok(id)
+    }
+
+    /// Handle the result of renaming the event.
+    ///
+    /// If the new name is taken, offer to merge into that event instead.
+    ///
+    /// ### Arguments
+    /// - result: `Result<()>` - The result of the rename.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from renaming, if any.
+    pub fn renamed(&mut self, result: Result<()>) -> Result<()> {
+        self.merge_target = match &result {
+            Err(Error::DuplicateName(target)) => Some(target.clone()),
+            _ => None,
+        };
+        result
+    }
+
+    /// Edit or delete occurrences, rename, merge or archive the event, or edit its meter, tags
+    /// or target interval.
+    ///
+    /// Changes to the data_base run in the background.
+    ///
+    /// ### Arguments
+    /// - message: `AppMessage` - The message to process.
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::Mutated` or `AppMessage::MutationFailed` when done.
+    pub fn update(&mut self, message: AppMessage, store: &Store) -> Command<AppMessage> {
+        let store = store.clone();
+        match message {
+            AppMessage::EditOccurrence(id) => {
+                let occurrence = self
+                    .history
+                    .iter()
+                    .flat_map(|history| history.occurrences.iter())
+                    .find(|occurrence| occurrence.id == id);
+                if let Some(occurrence) = occurrence {
+                    self.date = occurrence.date.to_string();
+                    self.editing = Some(id);
+                }
+                Command::none()
+            }
+            AppMessage::OccurrenceDate(date) => {
+                self.date = date;
+                Command::none()
+            }
+            AppMessage::CancelEdit => {
+                self.editing = None;
+                Command::none()
+            }
+            AppMessage::MoveOccurrence => match self.editing {
+                Some(id) => {
+                    let date = self.date.trim().to_string();
+                    mutate(move || {
+                        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| {
+                            Error::InvalidInput(format!("\"{}\" is not a YYYY-MM-DD date.", date))
+                        })?;
+                        store.move_occurrence(id, date)
+                    })
+                }
+                None => Command::none(),
+            },
+            AppMessage::DeleteOccurrence(id) => record(move || {
+                let (event_id, occurrence) = store.delete_occurrence(id)?;
+                Ok(Change::DeletedOccurrence(event_id, occurrence))
+            }),
+            AppMessage::RenameText(name) => {
+                self.name = name;
+                self.merge_target = None;
+                Command::none()
+            }
+            AppMessage::RenameEvent => match &self.history {
+                Some(history) if self.name.trim() != history.name => {
+                    let (old, new) = (history.name.clone(), self.name.clone());
+                    background(move || store.rename_event(&old, &new), AppMessage::Renamed)
+                }
+                _ => Command::none(),
+            },
+            AppMessage::MergeEvent => match (&self.history, self.merge_target.take()) {
+                (Some(history), Some(target)) => {
+                    let source = history.name.clone();
+                    background(
+                        move || Ok(Change::Merged(store.merge_events(&[&source], &target)?)),
+                        AppMessage::Merged,
+                    )
+                }
+                _ => Command::none(),
+            },
+            AppMessage::MeterUnitPicked(unit) => {
+                self.meter_unit = Some(unit);
+                Command::none()
+            }
+            AppMessage::ReadingText(reading) => {
+                self.reading = reading;
+                Command::none()
+            }
+            AppMessage::SaveMeter => match (&self.history, self.meter_unit) {
+                (Some(history), Some(unit)) => {
+                    let id = history.id;
+                    let reading = self.reading.clone();
+                    mutate(move || {
+                        let reading = parse_amount("meter reading", &reading)?;
+                        store.set_meter(id, Some(Meter { unit, reading }))
+                    })
+                }
+                _ => Command::none(),
+            },
+            AppMessage::RemoveMeter => match &self.history {
+                Some(history) => {
+                    let id = history.id;
+                    self.meter_unit = None;
+                    self.reading.clear();
+                    mutate(move || store.set_meter(id, None))
+                }
+                None => Command::none(),
+            },
+            AppMessage::EventTagsText(tags) => {
+                self.tags = tags;
+                Command::none()
+            }
+            AppMessage::SaveTags => match &self.history {
+                Some(history) => {
+                    let id = history.id;
+                    let tags = parse_tags(&self.tags);
+                    self.tags = tags.join(", ");
+                    mutate(move || store.set_tags(id, &tags))
+                }
+                None => Command::none(),
+            },
+            AppMessage::TargetText(target) => {
+                self.target = target;
+                Command::none()
+            }
+            AppMessage::SaveTarget => match &self.history {
+                Some(history) => {
+                    let id = history.id;
+                    let target = self.target.trim().to_string();
+                    mutate(move || {
+                        let days = match target.as_str() {
+                            "" => None,
+                            days => match days.parse::<u32>() {
+                                Ok(days) if days > 0 => Some(days),
+                                _ => {
+                                    return Err(Error::InvalidInput(format!(
+                                        "\"{}\" is not a number of days.",
+                                        days
+                                    )))
+                                }
+                            },
+                        };
+                        store.set_target_interval(id, days)
+                    })
+                }
+                None => Command::none(),
+            },
+            AppMessage::ArchiveEvent(archived) => match &self.history {
+                Some(history) => {
+                    let id = history.id;
+                    mutate(move || store.set_archived(id, archived))
+                }
+                None => Command::none(),
+            },
+            _ => Command::none(),
+        }
+    }
+
+    /// Create columns with header for the occurrence table.
+    ///
+    /// ### Arguments
+    /// - label: `&str` - The label for the column.
+    /// - width: `u16` - The width of the column.
+    ///
+    /// ### Returns
+    /// - `Column<AppMessage>` - The column with header.
+    fn make_column(label: &str, width: u16) -> Column<'a, AppMessage> {
+        let settings = Settings::new();
+        Column::new()
+            .spacing(settings.spacing())
+            .width(width)
+            .align_items(Alignment::Center)
+            .push(text(label).size(settings.text_size()))
+    }
+
+    /// View for EventDetail.
+    ///
+    /// ### Returns
+    /// - `Column<'a, AppMessage>` - The EventDetail page.
+    pub fn view(&self) -> Column<'a, AppMessage> {
+        let settings = Settings::new();
+        let mut content = Column::new()
+            .align_items(Alignment::Center)
+            .spacing(settings.spacing());
+        if self.loading {
+            content = content.push(text("Loading...").size(settings.text_size()));
+        }
+        if let Some(history) = &self.history {
+            let title = Text::new(history.name.clone())
+                .size(settings.text_size() + 10)
+                .horizontal_alignment(Horizontal::Center);
+            content = content.push(title);
+            // Rename, or merge if the new name is taken.
+            let mut rename_row = row![
+                text_input("Event Title", &self.name)
+                    .on_input(AppMessage::RenameText)
+                    .on_submit(AppMessage::RenameEvent)
+                    .size(settings.text_size())
+                    .width(300),
+                new_button(AppMessage::RenameEvent, text("Rename"), 120),
+                if history.archived {
+                    new_button(AppMessage::ArchiveEvent(false), text("Unarchive"), 140)
+                } else {
+                    new_button(AppMessage::ArchiveEvent(true), text("Archive"), 140)
+                },
+            ]
+            .spacing(settings.spacing())
+            .align_items(Alignment::Center);
+            if let Some(target) = &self.merge_target {
+                rename_row = rename_row.push(new_button(
+                    AppMessage::MergeEvent,
+                    text(format!("Merge into \"{}\"", target)),
+                    settings.add_button_size() + 150,
+                ));
+            }
+            content = content.push(rename_row);
+            // Measure the event by a meter, like an odometer, as well as by days.
+            let mut meter_row = row![
+                pick_list(
+                    &MeterUnit::ALL[..],
+                    self.meter_unit,
+                    AppMessage::MeterUnitPicked
+                )
+                .placeholder("Meter unit")
+                .text_size(settings.text_size())
+                .width(150),
+                text_input("Latest reading", &self.reading)
+                    .on_input(AppMessage::ReadingText)
+                    .on_submit(AppMessage::SaveMeter)
+                    .size(settings.text_size())
+                    .width(200),
+                new_button(AppMessage::SaveMeter, text("Save Meter"), 140),
+            ]
+            .spacing(settings.spacing())
+            .align_items(Alignment::Center);
+            if let Some(meter) = &history.meter {
+                meter_row = meter_row.push(new_button(
+                    AppMessage::RemoveMeter,
+                    text("Remove Meter"),
+                    160,
+                ));
+                let readings: Vec<f64> = history
+                    .occurrences
+                    .iter()
+                    .filter_map(|occurrence| occurrence.details.meter)
+                    .collect();
+                let summary = get_meter_summary(meter, &readings);
+                let mut stats = Vec::new();
+                if let Some(since_last) = summary.since_last {
+                    stats.push(format!(
+                        "{} {} since last",
+                        format_number(since_last),
+                        meter.unit
+                    ));
+                }
+                if let Some(average) = summary.average {
+                    stats.push(format!(
+                        "every {} {} on average",
+                        format_number(average),
+                        meter.unit
+                    ));
+                }
+                if !stats.is_empty() {
+                    content = content.push(text(stats.join(", ")).size(settings.text_size()));
+                }
+            }
+            content = content.push(meter_row);
+            // How often the event is meant to happen, to see when it is due.
+            content = content.push(
+                row![
+                    text("Due every").size(settings.text_size()),
+                    text_input("Days", &self.target)
+                        .on_input(AppMessage::TargetText)
+                        .on_submit(AppMessage::SaveTarget)
+                        .size(settings.text_size())
+                        .width(100),
+                    text("days").size(settings.text_size()),
+                    new_button(AppMessage::SaveTarget, text("Save Target"), 150),
+                ]
+                .spacing(settings.spacing())
+                .align_items(Alignment::Center),
+            );
+            // Tags group related events, and filter the Events page and the Calendar.
+            content = content.push(
+                row![
+                    text_input("Tags (comma separated)", &self.tags)
+                        .on_input(AppMessage::EventTagsText)
+                        .on_submit(AppMessage::SaveTags)
+                        .size(settings.text_size())
+                        .width(360),
+                    new_button(AppMessage::SaveTags, text("Save Tags"), 140),
+                ]
+                .spacing(settings.spacing())
+                .align_items(Alignment::Center),
+            );
+            // One row per occurrence, newest first.
+            let mut date_column = Self::make_column("Date", 250);
+            let mut interval_column = Self::make_column("Since Previous", 200);
+            let mut amount_column = Self::make_column("Amount", 150);
+            let mut cost_column = Self::make_column("Cost", 120);
+            let mut meter_column = Self::make_column("Meter", 150);
+            let mut note_column = Self::make_column("Note", 300);
+            let mut action_column = Self::make_column("", 350);
+            let occurrences = &history.occurrences;
+            for (i, occurrence) in occurrences.iter().enumerate().rev() {
+                let date = occurrence.date.format("%a, %b %e, %Y").to_string();
+                date_column = date_column.push(text(date).size(settings.text_size()).height(40));
+                let interval = match i {
+                    0 => "---".to_string(),
+                    _ => {
+                        let days = (occurrence.date - occurrences[i - 1].date).num_days();
+                        format!("{} day{}", days, if days != 1 { "s" } else { "" })
+                    }
+                };
+                interval_column =
+                    interval_column.push(text(interval).size(settings.text_size()).height(40));
+                let details = &occurrence.details;
+                let amount = match (details.quantity, &details.unit) {
+                    (Some(quantity), Some(unit)) => format!("{} {}", quantity, unit),
+                    (Some(quantity), None) => quantity.to_string(),
+                    (None, _) => String::new(),
+                };
+                amount_column =
+                    amount_column.push(text(amount).size(settings.text_size()).height(40));
+                let cost = details
+                    .cost
+                    .map(|cost| format!("{:.2}", cost))
+                    .unwrap_or_default();
+                cost_column = cost_column.push(text(cost).size(settings.text_size()).height(40));
+                let meter = details.meter.map(format_number).unwrap_or_default();
+                meter_column = meter_column.push(text(meter).size(settings.text_size()).height(40));
+                let note = details.note.clone().unwrap_or_default();
+                note_column = note_column.push(text(note).size(settings.text_size()).height(40));
+                let actions = if self.editing == Some(occurrence.id) {
+                    row![
+                        text_input("YYYY-MM-DD", &self.date)
+                            .on_input(AppMessage::OccurrenceDate)
+                            .on_submit(AppMessage::MoveOccurrence)
+                            .size(settings.text_size())
+                            .width(140),
+                        new_button(AppMessage::MoveOccurrence, text("Save"), 90),
+                        new_button(AppMessage::CancelEdit, text("Cancel"), 90),
+                    ]
+                } else {
+                    row![
+                        new_button(AppMessage::EditOccurrence(occurrence.id), text("Move"), 90),
+                        new_button(
+                            AppMessage::DeleteOccurrence(occurrence.id),
+                            text("Delete"),
+                            90
+                        ),
+                    ]
+                };
+                action_column = action_column.push(
+                    actions
+                        .spacing(settings.padding())
+                        .align_items(Alignment::Center),
+                );
+            }
+            content = content.push(
+                row![
+                    date_column,
+                    interval_column,
+                    amount_column,
+                    cost_column,
+                    meter_column,
+                    note_column,
+                    action_column
+                ]
+                .spacing(settings.spacing())
+                .align_items(Alignment::Start),
+            );
+        }
+        // Navigation buttons.
+        let event_button = new_button(
+            AppMessage::EventsWindow,
+            text("Events"),
+            settings.add_button_size(),
+        );
+        let calendar_button = new_button(
+            AppMessage::CalendarWindow,
+            text("Calendar"),
+            settings.add_button_size(),
+        );
+        content.push(
+            row![calendar_button, event_button]
+                .align_items(Alignment::Center)
+                .spacing(settings.spacing()),
+        )
+    }
+}
@@ -1,21 +1,352 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use iced::alignment::Horizontal;
-use iced::widget::{row, text, Column, Text};
-use iced::Alignment;
+use iced::theme::{self, Theme};
+use iced::widget::{button, checkbox, pick_list, row, text, Column, Row, Text};
+use iced::{Alignment, Color, Command, Element};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
 
-use crate::{app::AppMessage, settings::Settings, utils};
+use crate::{
+    app::AppMessage,
+    database::{Result, Store},
+    settings::Settings,
+    tags::TagFilter,
+    undo::Change,
+    utils,
+};
 
 /// Event state.
 #[derive(Debug, Clone)]
 pub struct EventOccurrence {
     pub name: String,
-    pub year: i32,
-    pub month: u32,
-    pub day: u32,
+    pub date: NaiveDate,
+    pub archived: bool,
+    pub cost: Option<f64>,
+    pub meter: Option<f64>,
+}
+
+/// An event as listed on the events page, and as printed by `since_when list --json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EventSummary {
+    pub id: i64,
+    pub name: String,
+    /// The date of the latest occurrence.
+    pub last: NaiveDate,
+    /// Days since the latest occurrence.
+    pub days_since: i32,
+    /// How many times the event happened.
+    pub occurrences: usize,
+    pub archived: bool,
+    /// What the event has cost, `None` if no occurrence has a cost.
+    pub cost: Option<CostSummary>,
+    /// How far the event's meter has run, `None` if it has no meter.
+    pub meter: Option<MeterSummary>,
+    /// The event's tags, sorted.
+    pub tags: Vec<String>,
+    /// How often the event is meant to happen, in days, if that was set.
+    pub target_interval_days: Option<u32>,
+    /// When the event will likely happen next, `None` if it has only happened once.
+    pub prediction: Option<Prediction>,
+    /// The days between occurrences, `None` if it has only happened once.
+    pub intervals: Option<IntervalStats>,
+}
+
+/// Statistics of the days between an event's occurrences.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct IntervalStats {
+    /// The number of intervals, one less than the number of occurrences.
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: i32,
+    pub max: i32,
+    /// The population standard deviation.
+    pub std_dev: f64,
+}
+
+/// The interval statistics the events page can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatColumn {
+    Mean,
+    Median,
+    Min,
+    Max,
+    StdDev,
+    Count,
+}
+
+/// StatColumn implementation.
+impl StatColumn {
+    /// Every statistic, in the order the columns are shown.
+    pub const ALL: [StatColumn; 6] = [
+        StatColumn::Mean,
+        StatColumn::Median,
+        StatColumn::Min,
+        StatColumn::Max,
+        StatColumn::StdDev,
+        StatColumn::Count,
+    ];
+
+    /// The column header.
+    ///
+    /// ### Returns
+    /// - `&'static str`
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatColumn::Mean => "Avg",
+            StatColumn::Median => "Median",
+            StatColumn::Min => "Min",
+            StatColumn::Max => "Max",
+            StatColumn::StdDev => "Std Dev",
+            StatColumn::Count => "Intervals",
+        }
+    }
+
+    /// The statistic for an event, as shown in the column.
+    ///
+    /// ### Arguments
+    /// - stats: `Option<&IntervalStats>` - The event's interval statistics.
+    ///
+    /// ### Returns
+    /// - `String` - `"---"` if the event has only happened once.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::events::StatColumn;
+    /// # use since_when_lib::utils::get_interval_stats;
+    /// let stats = get_interval_stats(&[1, 2]);
+    /// assert_eq!(StatColumn::Mean.format(stats.as_ref()), "1.5 days");
+    /// assert_eq!(StatColumn::Min.format(stats.as_ref()), "1 day");
+    /// assert_eq!(StatColumn::Count.format(stats.as_ref()), "2");
+    /// assert_eq!(StatColumn::Mean.format(None), "---");
+    /// ```
+    pub fn format(&self, stats: Option<&IntervalStats>) -> String {
+        let Some(stats) = stats else {
+            return "---".to_string();
+        };
+        let days = |days: f64| {
+            let days = utils::format_number(days);
+            let plural = if days != "1" { "s" } else { "" };
+            format!("{} day{}", days, plural)
+        };
+        match self {
+            StatColumn::Mean => days(stats.mean),
+            StatColumn::Median => days(stats.median),
+            StatColumn::Min => days(stats.min as f64),
+            StatColumn::Max => days(stats.max as f64),
+            StatColumn::StdDev => days(stats.std_dev),
+            StatColumn::Count => stats.count.to_string(),
+        }
+    }
+}
+
+/// When an event will likely happen next, going by its history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Prediction {
+    /// The latest occurrence plus the median interval.
+    pub date: NaiveDate,
+    /// The predicted date less the typical deviation from the median interval.
+    pub earliest: NaiveDate,
+    /// The predicted date plus the typical deviation from the median interval.
+    pub latest: NaiveDate,
+    /// The median days between occurrences.
+    pub interval: i32,
+}
+
+/// Where an event with a target interval stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueStatus {
+    OnTrack,
+    /// Due within the last fifth of the target interval.
+    DueSoon,
+    Overdue,
+}
+
+/// EventSummary implementation.
+impl EventSummary {
+    /// Days until the event is due, negative once it is overdue.
+    ///
+    /// ### Returns
+    /// - `Option<i32>` - `None` if the event has no target interval.
+    pub fn due_in(&self) -> Option<i32> {
+        self.target_interval_days
+            .map(|target| target as i32 - self.days_since)
+    }
+
+    /// Whether the event is on track, due soon or overdue.
+    ///
+    /// ### Returns
+    /// - `Option<DueStatus>` - `None` if the event has no target interval.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::events::{DueStatus, EventSummary};
+    /// let filter = |days_since| EventSummary {
+    ///     name: "Replace furnace filter".to_string(),
+    ///     days_since,
+    ///     target_interval_days: Some(90),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(filter(30).due_in(), Some(60));
+    /// assert_eq!(filter(30).status(), Some(DueStatus::OnTrack));
+    /// assert_eq!(filter(75).status(), Some(DueStatus::DueSoon));
+    /// assert_eq!(filter(90).status(), Some(DueStatus::DueSoon));
+    /// assert_eq!(filter(100).due_in(), Some(-10));
+    /// assert_eq!(filter(100).status(), Some(DueStatus::Overdue));
+    /// assert_eq!(EventSummary::default().status(), None);
+    /// ```
+    pub fn status(&self) -> Option<DueStatus> {
+        let target = self.target_interval_days? as i32;
+        let due_in = self.due_in()?;
+        Some(if due_in < 0 {
+            DueStatus::Overdue
+        } else if due_in * 5 <= target {
+            DueStatus::DueSoon
+        } else {
+            DueStatus::OnTrack
+        })
+    }
+}
+
+/// What an event has cost.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CostSummary {
+    /// Total spent on all occurrences.
+    pub total: f64,
+    /// Average cost of the occurrences with a cost.
+    pub average: f64,
+    /// Total spent per day since the first occurrence.
+    pub per_day: f64,
+}
+
+/// What an event's meter measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MeterUnit {
+    Miles,
+    #[serde(rename = "km")]
+    Kilometers,
+    Hours,
+}
+
+impl MeterUnit {
+    pub const ALL: [MeterUnit; 3] = [MeterUnit::Miles, MeterUnit::Kilometers, MeterUnit::Hours];
+
+    /// The unit as stored in the data_base and shown to the user.
+    ///
+    /// ### Returns
+    /// - `&'static str`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MeterUnit::Miles => "miles",
+            MeterUnit::Kilometers => "km",
+            MeterUnit::Hours => "hours",
+        }
+    }
+
+    /// Parse a unit stored by `as_str`.
+    ///
+    /// ### Arguments
+    /// - unit: `&str` - The stored unit.
+    ///
+    /// ### Returns
+    /// - `Option<MeterUnit>` - `None` if the unit is unknown.
+    pub fn parse(unit: &str) -> Option<MeterUnit> {
+        MeterUnit::ALL
+            .into_iter()
+            .find(|meter_unit| meter_unit.as_str() == unit)
+    }
+}
+
+impl fmt::Display for MeterUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// An event's meter, like a car's odometer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Meter {
+    pub unit: MeterUnit,
+    /// The latest reading entered, if any.
+    pub reading: Option<f64>,
+}
+
+/// How far an event's meter has run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MeterSummary {
+    pub unit: MeterUnit,
+    /// From the reading at the latest occurrence to the latest reading.
+    pub since_last: Option<f64>,
+    /// Average distance between the readings at occurrences.
+    pub average: Option<f64>,
+}
+
+/// A single occurrence of an event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Occurrence {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub details: OccurrenceDetails,
+}
+
+/// Optional details recorded with an occurrence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OccurrenceDetails {
+    /// Free text, like "used synthetic 5W-30".
+    pub note: Option<String>,
+    /// How much, like `20.0` gallons.
+    pub quantity: Option<f64>,
+    /// What the quantity is measured in, like "gal".
+    pub unit: Option<String>,
+    /// What the occurrence cost.
+    pub cost: Option<f64>,
+    /// The meter reading, like the odometer at an oil change.
+    pub meter: Option<f64>,
+}
+
+/// An event with all of its occurrences, oldest first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventHistory {
+    pub id: i64,
+    pub name: String,
+    pub occurrences: Vec<Occurrence>,
+    /// When the event was moved to the trash, in UTC, if it has been.
+    pub deleted_at: Option<NaiveDateTime>,
+    pub archived: bool,
+    pub meter: Option<Meter>,
+    /// The event's tags, sorted.
+    pub tags: Vec<String>,
+    /// How often the event is meant to happen, in days, if that was set.
+    pub target_interval_days: Option<u32>,
+}
+
+/// Events merged into another, as returned by `Store::merge_events`, for undoing the merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    /// The event kept, as it was before the merge.
+    pub target: EventHistory,
+    /// The events merged away.
+    pub sources: Vec<EventHistory>,
+    /// The event kept, as the merge left it.
+    pub merged: EventHistory,
 }
 
 /// Events page struct.
+///
+/// Holds the event details shown on the page, so drawing it never touches the data_base,
+/// and the events picked for merging.
 #[derive(Debug, Clone)]
-pub struct EventsPage {}
+pub struct EventsPage {
+    details: Vec<EventSummary>,
+    show_archived: bool,
+    loading: bool,
+    merging: bool,
+    selected: BTreeSet<String>,
+    merge_target: Option<String>,
+    stat_columns: BTreeSet<StatColumn>,
+}
 
 /// Default EventsPage implementation.
 impl Default for EventsPage {
@@ -27,21 +358,147 @@ impl Default for EventsPage {
 ///Events page implementation.
 impl<'a> EventsPage {
     pub fn new() -> EventsPage {
-        Self {}
+        Self {
+            details: Vec::new(),
+            show_archived: false,
+            loading: false,
+            merging: false,
+            selected: BTreeSet::new(),
+            merge_target: None,
+            stat_columns: BTreeSet::from([StatColumn::Mean]),
+        }
+    }
+
+    /// Pick events to merge and merge them, show archived events, or pick the interval
+    /// statistics to show.
+    ///
+    /// ### Arguments
+    /// - message: `AppMessage` - The message to process.
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::Merged` after merging.
+    pub fn update(&mut self, message: AppMessage, store: &Store) -> Command<AppMessage> {
+        match message {
+            AppMessage::ToggleMerge => {
+                self.merging = !self.merging;
+                self.selected.clear();
+                self.merge_target = None;
+            }
+            AppMessage::SelectEvent(name, selected) => {
+                if selected {
+                    self.selected.insert(name);
+                } else {
+                    self.selected.remove(&name);
+                }
+            }
+            AppMessage::MergeTarget(name) => {
+                self.merge_target = Some(name);
+            }
+            AppMessage::ShowArchived(show) => {
+                self.show_archived = show;
+            }
+            AppMessage::ShowStatColumn(column, show) => {
+                if show {
+                    self.stat_columns.insert(column);
+                } else {
+                    self.stat_columns.remove(&column);
+                }
+            }
+            AppMessage::MergeSelected => {
+                let Some(target) = self.merge_target.clone() else {
+                    return Command::none();
+                };
+                let sources: Vec<String> = self
+                    .selected
+                    .iter()
+                    .filter(|name| **name != target)
+                    .cloned()
+                    .collect();
+                if sources.is_empty() {
+                    return Command::none();
+                }
+                self.merging = false;
+                self.selected.clear();
+                self.merge_target = None;
+                let store = store.clone();
+                return utils::background(
+                    move || {
+                        let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+                        Ok(Change::Merged(store.merge_events(&sources, &target)?))
+                    },
+                    AppMessage::Merged,
+                );
+            }
+            _ => (),
+        }
+        Command::none()
+    }
+
+    /// Reload the event details from the data_base in the background.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::EventsLoaded` when done.
+    pub fn load(&mut self, store: &Store) -> Command<AppMessage> {
+        self.loading = true;
+        let store = store.clone();
+        utils::background(
+            move || utils::event_details(&store),
+            AppMessage::EventsLoaded,
+        )
+    }
+
+    /// Show freshly loaded event details.
+    ///
+    /// ### Arguments
+    /// - details: `Result<Vec<EventSummary>>` - The result of `load`.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from loading, if any.
+    pub fn loaded(&mut self, details: Result<Vec<EventSummary>>) -> Result<()> {
+        self.loading = false;
+        self.details = details?;
+        Ok(())
+    }
+
+    /// The loaded event details, including archived events.
+    ///
+    /// ### Returns
+    /// - `&[EventSummary]`
+    pub fn details(&self) -> &[EventSummary] {
+        &self.details
+    }
+
+    /// The events to list, leaving out archived events unless they were asked for, and events
+    /// without any of the picked tags.
+    ///
+    /// ### Arguments
+    /// - filter: `&'b TagFilter` - The tags picked to filter by.
+    ///
+    /// ### Returns
+    /// - `impl Iterator<Item = &EventSummary>`
+    fn shown<'b>(&'b self, filter: &'b TagFilter) -> impl Iterator<Item = &'b EventSummary> {
+        self.details
+            .iter()
+            .filter(|event| (self.show_archived || !event.archived) && filter.matches(&event.tags))
     }
 
     /// Create columns with header for events page.
     ///
     /// ### Arguments
     /// - label: `&str` - The label for the column.
+    /// - width: `u16` - The width of the column.
     ///
     /// ### Returns
     /// - `Column<AppMessage>` - The column with header.
-    fn make_column(label: &str) -> Column<AppMessage> {
+    fn make_column(label: &str, width: u16) -> Column<'_, AppMessage> {
         let settings = Settings::new();
         let mut column = Column::new()
             .spacing(settings.spacing())
-            .width(333)
+            .width(width)
             .align_items(Alignment::Center);
         let header = text(label).size(settings.text_size());
         let sep = text("_".repeat((label.len() * 5) + 5)).size(settings.text_size() / 4);
@@ -52,73 +509,288 @@ impl<'a> EventsPage {
 
     /// Create the event columns.
     ///
+    /// ### Arguments
+    /// - filter: `&TagFilter` - The tags picked to filter by.
+    ///
     /// ### Returns
-    /// - (`Column<'a, AppMessage>`, `Column<'a, AppMessage>`, `Column<'a, AppMessage>`, u16)
-    /// - The event column, date column, average column, and number of events.
-    fn event_columns() -> (
-        Column<'a, AppMessage>,
-        Column<'a, AppMessage>,
-        Column<'a, AppMessage>,
-    ) {
+    /// - `Vec<Column<'a, AppMessage>>` - The event and days since columns and the picked
+    ///   interval statistics, followed by the due column if any event has a target interval,
+    ///   the predicted next date column if any event happened more than once, the total spent,
+    ///   average cost and cost per day columns if any event has a cost, the meter since last
+    ///   and average interval columns if any event has a meter, and the tags column if any
+    ///   event has a tag.
+    fn event_columns(&self, filter: &TagFilter) -> Vec<Column<'a, AppMessage>> {
         let settings = Settings::new();
+        // Only show targets, costs, meters and tags when something has one, and narrow the
+        // columns to fit them.
+        let targets = self
+            .shown(filter)
+            .any(|event| event.target_interval_days.is_some());
+        let predictions = self.shown(filter).any(|event| event.prediction.is_some());
+        let costs = self.shown(filter).any(|event| event.cost.is_some());
+        let meters = self.shown(filter).any(|event| event.meter.is_some());
+        let tags = self.shown(filter).any(|event| !event.tags.is_empty());
+        let count = 2
+            + self.stat_columns.len()
+            + if targets { 1 } else { 0 }
+            + if predictions { 1 } else { 0 }
+            + if costs { 3 } else { 0 }
+            + if meters { 2 } else { 0 }
+            + if tags { 1 } else { 0 };
+        let width = (1000 / count as u16).max(180);
         // Create the columns.
-        let mut event_column = Self::make_column("Event");
-        let mut days_since_column = Self::make_column("Days  Since");
-        let mut avg_column = Self::make_column("Avg");
+        let mut event_column = Self::make_column("Event", width);
+        let mut days_since_column = Self::make_column("Days  Since", width);
+        let mut stat_columns: Vec<(StatColumn, Column<'a, AppMessage>)> = self
+            .stat_columns
+            .iter()
+            .map(|column| (*column, Self::make_column(column.label(), width)))
+            .collect();
+        let mut due_column = Self::make_column("Due", width);
+        let mut next_column = Self::make_column("Next", width);
+        let mut spent_column = Self::make_column("Spent", width);
+        let mut avg_cost_column = Self::make_column("Avg Cost", width);
+        let mut per_day_column = Self::make_column("Per Day", width);
+        let mut since_last_column = Self::make_column("Since Last", width);
+        let mut interval_column = Self::make_column("Avg Interval", width);
+        let mut tags_column = Self::make_column("Tags", width);
         // Create the event rows.
-        // event_details is a vector of tuples (event_name, days_since, average).
-        for (name, days_since, avg) in utils::event_details().iter() {
-            // Text for the event name.
-            let event_text = Text::new(name.clone())
-                .size(settings.text_size())
-                .horizontal_alignment(Horizontal::Center);
-            event_column = event_column.push(event_text);
-            // Text for the days since.
-            let plural = if *days_since != 1 { "s" } else { "" };
-            let days_since_text =
-                Text::new(format!("{} day{} ago", days_since, plural)).size(settings.text_size());
-            days_since_column = days_since_column.push(days_since_text);
-            // Text for the average.
-            if *avg != 0 {
-                let plural = if *avg > 1 { "s" } else { "" };
-                let average_text =
-                    Text::new(format!("{} day{}", avg, plural)).size(settings.text_size());
-                avg_column = avg_column.push(average_text);
+        for event in self.shown(filter) {
+            // Color the row by whether the event is on track, due soon or overdue.
+            let style = status_style(event.status());
+            if self.merging {
+                // Pick events to merge.
+                let name = event.name.clone();
+                let event_checkbox = checkbox(name.clone(), self.selected.contains(&name))
+                    .on_toggle(move |selected| AppMessage::SelectEvent(name.clone(), selected))
+                    .size(settings.text_size())
+                    .text_size(settings.text_size());
+                event_column = event_column.push(event_checkbox);
             } else {
-                let average_text = Text::new("---").size(settings.text_size());
-                avg_column = avg_column.push(average_text);
+                // The event name opens its history.
+                let label = if event.archived {
+                    format!("{} (archived)", event.name)
+                } else {
+                    event.name.clone()
+                };
+                let event_text = Text::new(label)
+                    .size(settings.text_size())
+                    .style(style)
+                    .horizontal_alignment(Horizontal::Center);
+                let event_button = button(event_text)
+                    .on_press(AppMessage::ShowEvent(event.name.clone()))
+                    .padding(0)
+                    .style(theme::Button::Text);
+                event_column = event_column.push(event_button);
             }
+            // Text for the days since.
+            let plural = if event.days_since != 1 { "s" } else { "" };
+            let days_since_text = Text::new(format!("{} day{} ago", event.days_since, plural))
+                .size(settings.text_size())
+                .style(style);
+            days_since_column = days_since_column.push(days_since_text);
+            // Text for the interval statistics.
+            stat_columns = stat_columns
+                .into_iter()
+                .map(|(stat, column)| {
+                    let stat_text = Text::new(stat.format(event.intervals.as_ref()))
+                        .size(settings.text_size())
+                        .style(style);
+                    (stat, column.push(stat_text))
+                })
+                .collect();
+            // Text for when the event is due.
+            let due = match event.due_in() {
+                Some(0) => "due today".to_string(),
+                Some(days) if days > 0 => {
+                    format!("due in {} day{}", days, if days != 1 { "s" } else { "" })
+                }
+                Some(days) => format!(
+                    "overdue by {} day{}",
+                    -days,
+                    if days != -1 { "s" } else { "" }
+                ),
+                None => "---".to_string(),
+            };
+            due_column = due_column.push(Text::new(due).size(settings.text_size()).style(style));
+            // Text for when the event will likely happen next.
+            let next = match event.prediction {
+                Some(prediction) if prediction.earliest == prediction.latest => {
+                    prediction.date.format("%b %e").to_string()
+                }
+                Some(prediction) => format!(
+                    "{} ({} - {})",
+                    prediction.date.format("%b %e"),
+                    prediction.earliest.format("%b %e"),
+                    prediction.latest.format("%b %e")
+                ),
+                None => "---".to_string(),
+            };
+            next_column = next_column.push(Text::new(next).size(settings.text_size()).style(style));
+            // Text for the costs.
+            let (spent, avg_cost, per_day) = match event.cost {
+                Some(cost) => (
+                    format!("{:.2}", cost.total),
+                    format!("{:.2}", cost.average),
+                    format!("{:.2}", cost.per_day),
+                ),
+                None => ("---".to_string(), "---".to_string(), "---".to_string()),
+            };
+            spent_column =
+                spent_column.push(Text::new(spent).size(settings.text_size()).style(style));
+            avg_cost_column =
+                avg_cost_column.push(Text::new(avg_cost).size(settings.text_size()).style(style));
+            per_day_column =
+                per_day_column.push(Text::new(per_day).size(settings.text_size()).style(style));
+            // Text for the meter.
+            let with_unit = |value: Option<f64>, unit| match value {
+                Some(value) => format!("{} {}", utils::format_number(value), unit),
+                None => "---".to_string(),
+            };
+            let (since_last, interval) = match event.meter {
+                Some(meter) => (
+                    with_unit(meter.since_last, meter.unit),
+                    with_unit(meter.average, meter.unit),
+                ),
+                None => ("---".to_string(), "---".to_string()),
+            };
+            since_last_column = since_last_column.push(
+                Text::new(since_last)
+                    .size(settings.text_size())
+                    .style(style),
+            );
+            interval_column =
+                interval_column.push(Text::new(interval).size(settings.text_size()).style(style));
+            // Chips for the tags, which filter by the tag when pressed.
+            let chips = event.tags.iter().map(|tag| filter.chip(tag).into());
+            tags_column = tags_column.push(Row::with_children(chips).spacing(settings.padding()));
+        }
+        let mut columns = vec![event_column, days_since_column];
+        columns.extend(stat_columns.into_iter().map(|(_, column)| column));
+        if targets {
+            columns.push(due_column);
+        }
+        if predictions {
+            columns.push(next_column);
+        }
+        if costs {
+            columns.extend([spent_column, avg_cost_column, per_day_column]);
+        }
+        if meters {
+            columns.extend([since_last_column, interval_column]);
         }
-        (event_column, days_since_column, avg_column)
+        if tags {
+            columns.push(tags_column);
+        }
+        columns
     }
 
     /// View the events page.
     ///
     /// ### Arguments
-    /// - `&self`
+    /// - filter: `&TagFilter` - The tags picked to filter by.
     ///
     /// ### Returns
     /// - `Element<'a, AppMessage>` - The events page.
-    pub fn view(&self) -> Column<'a, AppMessage> {
+    pub fn view(&self, filter: &TagFilter) -> Column<'a, AppMessage> {
         let settings = Settings::new();
-        // Get the event details and create the columns.
-        let (event_column, days_since_column, avg_column) = Self::event_columns();
+        // Create the columns from the event details.
+        let columns = self.event_columns(filter).into_iter().map(Element::from);
         // Align the columns into a row.
-        let event_row = row![event_column, days_since_column, avg_column]
+        let event_row = Row::with_children(columns)
             .spacing(settings.spacing())
             .align_items(Alignment::Center);
-        // Button for adding/updating events.
+        // Buttons for adding/updating and merging events, the trash, and importing and exporting.
         let calendar_button = utils::new_button(
             AppMessage::CalendarWindow,
             text("Add/Update Event"),
             settings.add_button_size() + 100,
         );
+        let merge_button = utils::new_button(
+            AppMessage::ToggleMerge,
+            text(if self.merging {
+                "Cancel"
+            } else {
+                "Merge Events"
+            }),
+            settings.add_button_size() + 50,
+        );
+        let trash_button = utils::new_button(
+            AppMessage::TrashWindow,
+            text("Trash"),
+            settings.add_button_size(),
+        );
+        let transfer_button = utils::new_button(
+            AppMessage::TransferWindow,
+            text("Import/Export"),
+            settings.add_button_size() + 50,
+        );
+        let mut button_row = row![calendar_button, merge_button, trash_button, transfer_button]
+            .spacing(settings.spacing())
+            .align_items(Alignment::Center);
+        if self.merging {
+            // Merge the checked events into the picked one.
+            let names: Vec<String> = self.shown(filter).map(|event| event.name.clone()).collect();
+            let target = pick_list(names, self.merge_target.clone(), AppMessage::MergeTarget)
+                .placeholder("Merge into...")
+                .text_size(settings.text_size())
+                .width(250);
+            let merge_button = utils::new_button(
+                AppMessage::MergeSelected,
+                text("Merge"),
+                settings.add_button_size(),
+            );
+            button_row = button_row.push(target).push(merge_button);
+        }
+        let archived_checkbox = checkbox("Show Archived", self.show_archived)
+            .on_toggle(AppMessage::ShowArchived)
+            .size(settings.text_size())
+            .text_size(settings.text_size());
+        // Pick the interval statistics to show.
+        let mut stats_row = row![text("Intervals:").size(settings.text_size())]
+            .spacing(settings.spacing())
+            .align_items(Alignment::Center);
+        for column in StatColumn::ALL {
+            stats_row = stats_row.push(
+                checkbox(column.label(), self.stat_columns.contains(&column))
+                    .on_toggle(move |show| AppMessage::ShowStatColumn(column, show))
+                    .size(settings.text_size())
+                    .text_size(settings.text_size()),
+            );
+        }
         // Arrange the content.
-        let content = Column::new()
+        let mut content = Column::new();
+        if self.loading {
+            content = content.push(text("Loading...").size(settings.text_size()));
+        }
+        if let Some(tag_row) = filter.view() {
+            content = content.push(tag_row);
+        }
+        let content = content
             .push(event_row)
-            .push(calendar_button)
+            .push(row![archived_checkbox, stats_row].spacing(settings.spacing() + 40))
+            .push(button_row)
             .align_items(Alignment::Center)
             .spacing(settings.spacing() + 40);
         content
     }
 }
+
+/// The text color for an event's due status.
+///
+/// ### Arguments
+/// - status: `Option<DueStatus>` - The status, `None` if the event has no target interval.
+///
+/// ### Returns
+/// - `theme::Text` - Red if overdue, yellow if due soon, green if on track, and the default
+///   color otherwise.
+fn status_style(status: Option<DueStatus>) -> theme::Text {
+    // The app's Nord theme has no warning color, so use Nord's own yellow.
+    let palette = Theme::Nord.palette();
+    match status {
+        Some(DueStatus::Overdue) => theme::Text::Color(palette.danger),
+        Some(DueStatus::DueSoon) => theme::Text::Color(Color::from_rgb8(0xEB, 0xCB, 0x8B)),
+        Some(DueStatus::OnTrack) => theme::Text::Color(palette.success),
+        None => theme::Text::Default,
+    }
+}
@@ -0,0 +1,832 @@
+use chrono::{Datelike, Days, Duration, NaiveDate, NaiveDateTime, Utc, Weekday};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::{
+    database::{Error, Result, Store},
+    events::OccurrenceDetails,
+    transfer::{ImportReport, ImportRow, RowError},
+    utils,
+};
+
+/// Identifies the app in exported calendars.
+const PRODID: &str = "-//since_when//Since When//EN";
+/// Ends the UIDs of exported events, keeping them apart from other apps' events.
+const UID_DOMAIN: &str = "since_when";
+
+/// Write every occurrence as an all-day iCalendar event.
+///
+/// Each event has a UID made from the event and occurrence ids, so importing the file again
+/// updates the events already in the calendar rather than adding them twice.
+///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+/// - due_dates: `bool` - `true` to add when each event outside the archive is due, or
+///   expected if it has no target interval.
+/// - stamp: `NaiveDateTime` - When the calendar was written, in UTC.
+///
+/// ### Returns
+/// - `Result<String>` - The `.ics` calendar.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::database::Store;
+/// # use since_when_lib::ical::export_ics;
+/// # use since_when_lib::utils::get_date;
+/// let store = Store::open_in_memory().unwrap();
+/// let id = store.add_event("Replace furnace filter", get_date(2023, 4, 1)).unwrap();
+/// store.set_target_interval(id, Some(90)).unwrap();
+/// let stamp = get_date(2023, 4, 2).and_hms_opt(12, 0, 0).unwrap();
+///
+/// let ics = export_ics(&store, true, stamp).unwrap();
+/// assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+/// assert!(ics.contains(
+///     "BEGIN:VEVENT\r\n\
+///     UID:occurrence-1@since_when\r\n\
+///     DTSTAMP:20230402T120000Z\r\n\
+///     DTSTART;VALUE=DATE:20230401\r\n\
+///     DTEND;VALUE=DATE:20230402\r\n\
+///     SUMMARY:Replace furnace filter\r\n"
+/// ));
+/// // Due 90 days after the last occurrence.
+/// assert!(ics.contains("UID:due-1@since_when\r\n"));
+/// assert!(ics.contains("DTSTART;VALUE=DATE:20230630\r\n"));
+/// assert!(ics.ends_with("END:VCALENDAR\r\n"));
+///
+/// let ics = export_ics(&store, false, stamp).unwrap();
+/// assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+/// ```
+pub fn export_ics(store: &Store, due_dates: bool, stamp: NaiveDateTime) -> Result<String> {
+    let mut ics = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        &format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN",
+    ] {
+        ics += &format!("{}\r\n", line);
+    }
+    // Occurrences keep their id when their event is merged, so the UID leaves out the event.
+    for (event, occurrence) in store.all_occurrences()? {
+        ics += &vevent(
+            &format!("occurrence-{}", occurrence.id),
+            occurrence.date,
+            &event,
+            occurrence.details.note.as_deref(),
+            stamp,
+        );
+    }
+    if due_dates {
+        for event in utils::event_details(store)? {
+            if event.archived {
+                continue;
+            }
+            let (date, summary, description) = match (event.target_interval_days, event.prediction)
+            {
+                (Some(days), _) => (
+                    event.last + Duration::days(days as i64),
+                    format!("{} (due)", event.name),
+                    format!("Every {} days, last on {}.", days, event.last),
+                ),
+                (None, Some(prediction)) => (
+                    prediction.date,
+                    format!("{} (expected)", event.name),
+                    format!(
+                        "Usually every {} days, last on {}.",
+                        prediction.interval, event.last
+                    ),
+                ),
+                (None, None) => continue,
+            };
+            ics += &vevent(
+                &format!("due-{}", event.id),
+                date,
+                &summary,
+                Some(&description),
+                stamp,
+            );
+        }
+    }
+    ics += "END:VCALENDAR\r\n";
+    Ok(ics)
+}
+
+/// Write every occurrence to an `.ics` calendar file.
+///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+/// - path: `&Path` - The file to write, replaced if it exists.
+/// - due_dates: `bool` - `true` to add when each event is due or expected next.
+///
+/// ### Returns
+/// - `Result<usize>` - The number of calendar events written, or `Error::InvalidInput` if the
+///   file cannot be written.
+pub fn export_ics_file(store: &Store, path: &Path, due_dates: bool) -> Result<usize> {
+    let ics = export_ics(store, due_dates, Utc::now().naive_utc())?;
+    fs::write(path, &ics)
+        .map_err(|e| Error::InvalidInput(format!("Could not write {}: {}", path.display(), e)))?;
+    Ok(ics.matches("BEGIN:VEVENT\r\n").count())
+}
+
+/// Write an all-day calendar event.
+///
+/// ### Arguments
+/// - uid: `&str` - What makes the event unique, before `@since_when`.
+/// - date: `NaiveDate` - The day of the event.
+/// - summary: `&str` - The title of the event.
+/// - description: `Option<&str>` - More about the event, like a note.
+/// - stamp: `NaiveDateTime` - When the calendar was written, in UTC.
+///
+/// ### Returns
+/// - `String` - The `VEVENT`, with each line ending in `\r\n`.
+fn vevent(
+    uid: &str,
+    date: NaiveDate,
+    summary: &str,
+    description: Option<&str>,
+    stamp: NaiveDateTime,
+) -> String {
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}@{}", uid, UID_DOMAIN),
+        format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")),
+        format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+        // All-day events end the day after, exclusively.
+        format!(
+            "DTEND;VALUE=DATE:{}",
+            (date + Duration::days(1)).format("%Y%m%d")
+        ),
+        format!("SUMMARY:{}", escape_text(summary)),
+    ];
+    if let Some(description) = description {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    lines.push("TRANSP:TRANSPARENT".to_string());
+    lines.push("END:VEVENT".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Escape text for an iCalendar property value.
+///
+/// ### Arguments
+/// - text: `&str` - The text.
+///
+/// ### Returns
+/// - `String` - The text with backslashes, semicolons, commas and line breaks escaped.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line so no line is longer than 75 octets.
+///
+/// ### Arguments
+/// - line: `&str` - The content line, without its line break.
+///
+/// ### Returns
+/// - `String` - The line, continued on lines starting with a space, ending in `\r\n`.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        // Continuation lines lose one octet to the leading space.
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded += "\r\n";
+    folded
+}
+
+/// How often a repeating calendar event repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// How a calendar event repeats, from its `RRULE` property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    /// Repeats every `interval` days, weeks, months or years.
+    pub interval: u32,
+    /// How many times the event happens, counting the first.
+    pub count: Option<u32>,
+    /// The last day the event can happen.
+    pub until: Option<NaiveDate>,
+    /// The days a weekly event happens on, or only the day it starts on if empty.
+    pub weekdays: Vec<Weekday>,
+}
+
+impl Rule {
+    /// Read an `RRULE` value, like `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH`.
+    ///
+    /// Only `FREQ`, `INTERVAL`, `COUNT`, `UNTIL` and, for weekly rules, `BYDAY` without
+    /// numbers are understood.
+    ///
+    /// ### Arguments
+    /// - value: `&str` - The value of the `RRULE` property.
+    ///
+    /// ### Returns
+    /// - `Result<Rule>` - `Error::InvalidInput` if the rule is malformed or not understood.
+    fn parse(value: &str) -> Result<Rule> {
+        let invalid = |part: &str| {
+            Error::InvalidInput(format!(
+                "The repeating rule part {} is not supported.",
+                part
+            ))
+        };
+        let mut frequency = None;
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            weekdays: Vec::new(),
+        };
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(part)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|&interval| interval > 0)
+                        .ok_or_else(|| invalid(part))?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid(part))?),
+                "UNTIL" => rule.until = Some(parse_date(value)?),
+                "BYDAY" => {
+                    rule.weekdays = value
+                        .split(',')
+                        .map(|day| parse_weekday(day).ok_or_else(|| invalid(part)))
+                        .collect::<Result<_>>()?
+                }
+                // Only changes which weeks BYDAY counts from when repeating every few weeks.
+                "WKST" => (),
+                _ => return Err(invalid(part)),
+            }
+        }
+        rule.frequency = frequency
+            .ok_or_else(|| Error::InvalidInput("The repeating rule has no FREQ.".to_string()))?;
+        if !rule.weekdays.is_empty() && rule.frequency != Frequency::Weekly {
+            return Err(invalid("BYDAY"));
+        }
+        Ok(rule)
+    }
+}
+
+/// An event read from an `.ics` calendar, a `VEVENT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    /// The line of the file the event starts on, for error messages.
+    pub line: usize,
+    pub uid: Option<String>,
+    pub summary: String,
+    /// The day the event starts on. Times and time zones are dropped.
+    pub start: NaiveDate,
+    pub description: Option<String>,
+    /// How the event repeats, if it does.
+    pub rule: Option<Rule>,
+    /// Days a repeating event skips, from its `EXDATE` properties.
+    pub exceptions: Vec<NaiveDate>,
+    /// The day of the repeating event with the same UID that this event replaces, if any.
+    pub recurrence_id: Option<NaiveDate>,
+    /// Whether the event was cancelled.
+    pub cancelled: bool,
+}
+
+impl CalendarEvent {
+    /// The days the event happens on within a date range.
+    ///
+    /// ### Arguments
+    /// - from: `Option<NaiveDate>` - The first day to include, or `None` for no limit.
+    /// - to: `NaiveDate` - The last day to include, so events repeating forever end.
+    ///
+    /// ### Returns
+    /// - `Vec<NaiveDate>` - The days, oldest first.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::ical::Calendar;
+    /// # use since_when_lib::utils::get_date;
+    /// let calendar = Calendar::parse(
+    ///     "BEGIN:VCALENDAR\r\n\
+    ///     BEGIN:VEVENT\r\n\
+    ///     SUMMARY:Mow the lawn\r\n\
+    ///     DTSTART;VALUE=DATE:20230403\r\n\
+    ///     RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH\r\n\
+    ///     EXDATE;VALUE=DATE:20230417\r\n\
+    ///     END:VEVENT\r\n\
+    ///     END:VCALENDAR\r\n",
+    /// )
+    /// .unwrap();
+    /// let dates = calendar.events[0].dates(Some(get_date(2023, 4, 4)), get_date(2023, 5, 1));
+    /// assert_eq!(
+    ///     dates,
+    ///     [get_date(2023, 4, 6), get_date(2023, 4, 20), get_date(2023, 5, 1)]
+    /// );
+    ///
+    /// // Rules repeating past the last date chrono can represent stop there.
+    /// for rule in [
+    ///     "FREQ=YEARLY;INTERVAL=300000",
+    ///     "FREQ=DAILY;INTERVAL=200000000",
+    ///     "FREQ=WEEKLY;INTERVAL=20000000;BYDAY=MO",
+    /// ] {
+    ///     let calendar = Calendar::parse(&format!(
+    ///         "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Rarely\r\n\
+    ///         DTSTART;VALUE=DATE:20230403\r\nRRULE:{}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+    ///         rule
+    ///     ))
+    ///     .unwrap();
+    ///     let dates = calendar.events[0].dates(None, chrono::NaiveDate::MAX);
+    ///     assert_eq!(dates, [get_date(2023, 4, 3)]);
+    /// }
+    /// ```
+    pub fn dates(&self, from: Option<NaiveDate>, to: NaiveDate) -> Vec<NaiveDate> {
+        let wanted = |date: &NaiveDate| {
+            from.is_none_or(|from| *date >= from) && !self.exceptions.contains(date)
+        };
+        let Some(rule) = &self.rule else {
+            return Some(self.start)
+                .filter(|date| *date <= to && wanted(date))
+                .into_iter()
+                .collect();
+        };
+        let last = rule.until.map_or(to, |until| until.min(to));
+        let mut dates = Vec::new();
+        let mut count = 0;
+        for period in 0.. {
+            // Events repeating past the last date chrono can represent stop there.
+            let Some((period_start, days)) = self.period(rule, period) else {
+                break;
+            };
+            if period_start > last {
+                break;
+            }
+            for date in days.into_iter().filter(|date| *date >= self.start) {
+                // Every instance counts towards COUNT, even those outside the range.
+                if date > last || rule.count.is_some_and(|count_limit| count >= count_limit) {
+                    return dates;
+                }
+                count += 1;
+                if wanted(&date) {
+                    dates.push(date);
+                }
+            }
+        }
+        dates
+    }
+
+    /// The days a repeating event may happen on in one of its periods.
+    ///
+    /// ### Arguments
+    /// - rule: `&Rule` - How the event repeats.
+    /// - period: `u64` - Which period, `0` for the one the event starts in.
+    ///
+    /// ### Returns
+    /// - `Option<(NaiveDate, Vec<NaiveDate>)>` - The first day of the period, and the days in
+    ///   it, oldest first. A monthly event on the 31st has no day in shorter months. `None` once
+    ///   the period is past the last date chrono can represent.
+    fn period(&self, rule: &Rule, period: u64) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = period.checked_mul(rule.interval as u64)?;
+        match rule.frequency {
+            Frequency::Daily => {
+                let day = self.start.checked_add_days(Days::new(step))?;
+                Some((day, vec![day]))
+            }
+            Frequency::Weekly if rule.weekdays.is_empty() => {
+                let day = self
+                    .start
+                    .checked_add_days(Days::new(step.checked_mul(7)?))?;
+                Some((day, vec![day]))
+            }
+            Frequency::Weekly => {
+                let monday = self
+                    .start
+                    .checked_sub_days(
+                        Days::new(self.start.weekday().num_days_from_monday() as u64),
+                    )?
+                    .checked_add_days(Days::new(step.checked_mul(7)?))?;
+                let mut days: Vec<NaiveDate> = rule
+                    .weekdays
+                    .iter()
+                    .filter_map(|day| {
+                        monday.checked_add_days(Days::new(day.num_days_from_monday() as u64))
+                    })
+                    .collect();
+                days.sort();
+                days.dedup();
+                Some((monday, days))
+            }
+            Frequency::Monthly | Frequency::Yearly => {
+                let months = if rule.frequency == Frequency::Yearly {
+                    step.checked_mul(12)?
+                } else {
+                    step
+                };
+                let month = (self.start.year() as i64 * 12 + self.start.month0() as i64)
+                    .checked_add(i64::try_from(months).ok()?)?;
+                let year = i32::try_from(month / 12).ok()?;
+                let month = (month % 12) as u32 + 1;
+                Some((
+                    NaiveDate::from_ymd_opt(year, month, 1)?,
+                    NaiveDate::from_ymd_opt(year, month, self.start.day())
+                        .into_iter()
+                        .collect(),
+                ))
+            }
+        }
+    }
+}
+
+/// The events read from an `.ics` calendar.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calendar {
+    pub events: Vec<CalendarEvent>,
+    /// The events that could not be read, by line.
+    pub errors: Vec<RowError>,
+}
+
+impl Calendar {
+    /// Read the events of an `.ics` calendar.
+    ///
+    /// ### Arguments
+    /// - ics: `&str` - The calendar.
+    ///
+    /// ### Returns
+    /// - `Result<Calendar>` - `Error::InvalidInput` if the text is not a calendar at all.
+    pub fn parse(ics: &str) -> Result<Calendar> {
+        let lines = unfold(ics);
+        if !lines
+            .iter()
+            .any(|(_, line)| line.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+        {
+            return Err(Error::InvalidInput(
+                "The file is not an iCalendar file, it has no BEGIN:VCALENDAR.".to_string(),
+            ));
+        }
+        let mut calendar = Calendar::default();
+        // The line the current VEVENT starts on, its properties, and how deep in it
+        // components like VALARM are nested.
+        let mut event: Option<(usize, Vec<(String, String)>)> = None;
+        let mut depth = 0;
+        for (number, line) in lines {
+            let (name, value) = property(&line);
+            match (name.as_str(), event.as_mut()) {
+                ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                    event = Some((number, Vec::new()));
+                }
+                ("BEGIN", Some(_)) => depth += 1,
+                ("END", Some(_)) if depth > 0 => depth -= 1,
+                ("END", Some(_)) => {
+                    let (start, properties) = event.take().unwrap_or_default();
+                    match read_event(start, &properties) {
+                        Ok(event) => calendar.events.push(event),
+                        Err(e) => calendar.errors.push(RowError {
+                            line: start,
+                            message: e.to_string(),
+                        }),
+                    }
+                }
+                (_, Some((_, properties))) if depth == 0 => {
+                    properties.push((line.clone(), value));
+                }
+                _ => (),
+            }
+        }
+        Ok(calendar)
+    }
+
+    /// Read the events of an `.ics` calendar file.
+    ///
+    /// ### Arguments
+    /// - path: `&Path` - The file to read.
+    ///
+    /// ### Returns
+    /// - `Result<Calendar>` - `Error::InvalidInput` if the file cannot be read as a calendar.
+    pub fn read(path: &Path) -> Result<Calendar> {
+        let ics = fs::read_to_string(path).map_err(|e| {
+            Error::InvalidInput(format!("Could not read {}: {}", path.display(), e))
+        })?;
+        Self::parse(&ics)
+    }
+
+    /// The titles of the calendar's events, to pick which event each is imported as.
+    ///
+    /// ### Returns
+    /// - `BTreeMap<String, usize>` - `{summary: number of calendar events}`.
+    pub fn summaries(&self) -> BTreeMap<String, usize> {
+        let mut summaries = BTreeMap::new();
+        for event in &self.events {
+            *summaries.entry(event.summary.clone()).or_default() += 1;
+        }
+        summaries
+    }
+
+    /// The occurrences to add for the calendar's events within a date range.
+    ///
+    /// Repeating events are expanded, leaving out cancelled days and days replaced by another
+    /// event with the same UID.
+    ///
+    /// ### Arguments
+    /// - mapping: `&HashMap<String, String>` - `{summary: event}`, the event to import each
+    ///   summary as. Summaries left out are imported under their own name, and those mapped to
+    ///   an empty name are skipped.
+    /// - from: `Option<NaiveDate>` - The first day to import, or `None` for no limit.
+    /// - to: `NaiveDate` - The last day to import.
+    ///
+    /// ### Returns
+    /// - `Vec<ImportRow>` - The occurrences, with the line of the event they came from.
+    pub fn rows(
+        &self,
+        mapping: &HashMap<String, String>,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+    ) -> Vec<ImportRow> {
+        let replaced: HashSet<(&str, NaiveDate)> = self
+            .events
+            .iter()
+            .filter_map(|event| Some((event.uid.as_deref()?, event.recurrence_id?)))
+            .collect();
+        let mut rows = Vec::new();
+        for event in self.events.iter().filter(|event| !event.cancelled) {
+            let name = mapping.get(&event.summary).unwrap_or(&event.summary).trim();
+            if name.is_empty() {
+                continue;
+            }
+            for date in event.dates(from, to) {
+                let replaced = match (&event.rule, event.recurrence_id, event.uid.as_deref()) {
+                    (Some(_), None, Some(uid)) => replaced.contains(&(uid, date)),
+                    _ => false,
+                };
+                if !replaced {
+                    rows.push(ImportRow {
+                        line: event.line,
+                        event: name.to_string(),
+                        date,
+                        details: OccurrenceDetails {
+                            note: event.description.clone(),
+                            ..Default::default()
+                        },
+                    });
+                }
+            }
+        }
+        rows
+    }
+
+    /// Add the calendar's events within a date range as occurrences, creating events that do
+    /// not exist yet.
+    ///
+    /// Occurrences are added the same way `Store::update_event` adds them, so days an event
+    /// already happened on are skipped.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    /// - mapping: `&HashMap<String, String>` - `{summary: event}`, as for `rows`.
+    /// - from: `Option<NaiveDate>` - The first day to import, or `None` for no limit.
+    /// - to: `NaiveDate` - The last day to import.
+    /// - dry_run: `bool` - `true` to report what would change without saving anything.
+    ///
+    /// ### Returns
+    /// - `Result<ImportReport>` - What was, or would be, added, with the calendar events that
+    ///   could not be read.
+    ///
+    /// ### Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::ical::Calendar;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Haircut", get_date(2023, 1, 10)).unwrap();
+    /// let calendar = Calendar::parse(
+    ///     "BEGIN:VCALENDAR\r\n\
+    ///     BEGIN:VEVENT\r\n\
+    ///     UID:cut@example.com\r\n\
+    ///     SUMMARY:Barber\r\n\
+    ///     DTSTART:20230110T090000Z\r\n\
+    ///     RRULE:FREQ=MONTHLY;COUNT=4\r\n\
+    ///     END:VEVENT\r\n\
+    ///     BEGIN:VEVENT\r\n\
+    ///     UID:cut@example.com\r\n\
+    ///     RECURRENCE-ID:20230310T090000Z\r\n\
+    ///     SUMMARY:Barber\r\n\
+    ///     DTSTART:20230314T090000Z\r\n\
+    ///     DESCRIPTION:Moved\\, they were closed\r\n\
+    ///     END:VEVENT\r\n\
+    ///     BEGIN:VEVENT\r\n\
+    ///     SUMMARY:Dentist\r\n\
+    ///     DTSTART;VALUE=DATE:20230301\r\n\
+    ///     END:VEVENT\r\n\
+    ///     BEGIN:VEVENT\r\n\
+    ///     SUMMARY:Broken\r\n\
+    ///     END:VEVENT\r\n\
+    ///     END:VCALENDAR\r\n",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(calendar.errors[0].line, 19);
+    ///
+    /// let mapping = HashMap::from([("Barber".to_string(), "Haircut".to_string())]);
+    /// let report = calendar
+    ///     .import(&store, &mapping, None, get_date(2023, 3, 31), false)
+    ///     .unwrap();
+    /// // January 10th is already recorded, and April 10th is after the range.
+    /// assert_eq!((report.added, report.skipped), (3, 1));
+    /// assert_eq!(report.created, ["Dentist"]);
+    ///
+    /// let id = store.get_event_id("Haircut").unwrap();
+    /// let history = store.event_history(id).unwrap();
+    /// let dates: Vec<_> = history.occurrences.iter().map(|o| o.date).collect();
+    /// assert_eq!(
+    ///     dates,
+    ///     [get_date(2023, 1, 10), get_date(2023, 2, 10), get_date(2023, 3, 14)]
+    /// );
+    /// assert_eq!(history.occurrences[2].details.note.as_deref(), Some("Moved, they were closed"));
+    /// ```
+    pub fn import(
+        &self,
+        store: &Store,
+        mapping: &HashMap<String, String>,
+        from: Option<NaiveDate>,
+        to: NaiveDate,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        let rows = self.rows(mapping, from, to);
+        let mut report = store.import_occurrences(&rows, dry_run)?;
+        report.errors.extend(self.errors.iter().cloned());
+        report.errors.sort_by_key(|error| error.line);
+        Ok(report)
+    }
+}
+
+/// Read a calendar event from its properties.
+///
+/// ### Arguments
+/// - line: `usize` - The line the event starts on.
+/// - properties: `&[(String, String)]` - The event's content lines and their values.
+///
+/// ### Returns
+/// - `Result<CalendarEvent>` - `Error::InvalidInput` if the event has no summary or start,
+///   or a date or repeating rule cannot be read.
+fn read_event(line: usize, properties: &[(String, String)]) -> Result<CalendarEvent> {
+    let (mut uid, mut summary, mut start, mut description, mut rule) =
+        (None, None, None, None, None);
+    let (mut exceptions, mut recurrence_id, mut cancelled) = (Vec::new(), None, false);
+    for (content, value) in properties {
+        let (name, _) = property(content);
+        match name.as_str() {
+            "UID" => uid = Some(value.clone()),
+            "SUMMARY" => summary = Some(unescape_text(value).trim().to_string()),
+            "DESCRIPTION" => description = Some(unescape_text(value)),
+            "DTSTART" => start = Some(parse_date(value)?),
+            "RRULE" => rule = Some(Rule::parse(value)?),
+            "EXDATE" => {
+                for date in value.split(',') {
+                    exceptions.push(parse_date(date)?);
+                }
+            }
+            "RECURRENCE-ID" => recurrence_id = Some(parse_date(value)?),
+            "STATUS" => cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => (),
+        }
+    }
+    Ok(CalendarEvent {
+        line,
+        uid,
+        summary: summary
+            .filter(|summary| !summary.is_empty())
+            .ok_or_else(|| Error::InvalidInput("The calendar event has no SUMMARY.".to_string()))?,
+        start: start
+            .ok_or_else(|| Error::InvalidInput("The calendar event has no DTSTART.".to_string()))?,
+        description: description.filter(|description| !description.is_empty()),
+        rule,
+        exceptions,
+        recurrence_id,
+        cancelled,
+    })
+}
+
+/// Join folded content lines back together.
+///
+/// ### Arguments
+/// - ics: `&str` - The calendar.
+///
+/// ### Returns
+/// - `Vec<(usize, String)>` - The line each content line starts on, and the content line.
+fn unfold(ics: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (number, line) in ics.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => *last += rest,
+            _ if line.is_empty() => (),
+            _ => lines.push((number + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// Split a content line into its upper case property name and its value.
+///
+/// ### Arguments
+/// - line: `&str` - The content line, like `DTSTART;VALUE=DATE:20230401`.
+///
+/// ### Returns
+/// - `(String, String)` - Like `("DTSTART", "20230401")`. Parameters are dropped.
+fn property(line: &str) -> (String, String) {
+    // The value starts at the first colon outside a quoted parameter value.
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    });
+    let (head, value) = match colon {
+        Some(i) => (&line[..i], &line[i + 1..]),
+        None => (line, ""),
+    };
+    let name = head.split(';').next().unwrap_or_default();
+    (name.trim().to_uppercase(), value.trim().to_string())
+}
+
+/// Read the day from an iCalendar date or date-time, like `20230401` or `20230401T090000Z`.
+///
+/// ### Arguments
+/// - value: `&str` - The date or date-time.
+///
+/// ### Returns
+/// - `Result<NaiveDate>` - `Error::InvalidInput` if there is no date.
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    let value = value.trim();
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| Error::InvalidInput(format!("\"{}\" is not an iCalendar date.", value)))
+}
+
+/// Read a `BYDAY` day, like `MO`.
+///
+/// ### Arguments
+/// - day: `&str` - The day.
+///
+/// ### Returns
+/// - `Option<Weekday>` - `None` for anything but the two letter days.
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Undo `escape_text`.
+///
+/// ### Arguments
+/// - text: `&str` - The escaped text.
+///
+/// ### Returns
+/// - `String` - The text.
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => (),
+        }
+    }
+    unescaped
+}
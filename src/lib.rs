@@ -1,8 +1,16 @@
 pub mod add_event;
 pub mod app;
 pub mod calendar;
+pub mod cli;
 pub mod database;
+pub mod event_detail;
 pub mod events;
+pub mod ical;
+pub mod migrations;
 pub mod settings;
+pub mod tags;
+pub mod transfer;
+pub mod trash;
+pub mod undo;
 pub mod utils;
 extern crate log;
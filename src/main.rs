@@ -20,18 +20,26 @@ The app has three windows:
          - A text box allows the user to enter a new event title.
          - A button labeled "Add Event" allows the user to add the event to the data_base.
          - Clicking the "Update Event" button adds an occurrence of the event to the data_base.
-         - Clicking the "Delete Event" button removes the event from the data_base.
+         - Clicking the "Delete Event" button moves the event to the trash.
          - Buttons for returning to the main page and the calendar page are displayed at the
          bottom of the page.
+The same events can be managed without the window from the command line, see `cli::USAGE`.
  */
-#![windows_subsystem = "windows"] // Prevents windows from opening a terminal window.
+// Prevents windows from opening a terminal window. Commands attach to the terminal they are
+// run from instead, see `attach_console`.
+#![windows_subsystem = "windows"]
 
 use env_logger::Env;
 use iced::{Application, Settings};
 use since_when_lib::app::SinceWhen;
+use since_when_lib::database::Store;
+use since_when_lib::{cli, settings};
+use std::{env, process};
 extern crate log;
 
 /// The main function.
+///
+/// Runs a command given on the command line, or opens the app window without one.
 pub fn main() -> iced::Result {
     // Initialize the logger.
     let env = Env::default()
@@ -39,6 +47,53 @@ pub fn main() -> iced::Result {
         .write_style_or("MY_LOG_STYLE", "always");
     env_logger::init_from_env(env);
 
+    // Read the data_base location and any command from the command line.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (database, subcommand) = match cli::parse(args, chrono::Local::now().date_naive()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            attach_console();
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    // Resolve the data_base location from the command line, environment, or config file.
+    let settings = settings::Settings::load(database);
+
+    // Run a command without opening the app window.
+    if let Some(subcommand) = subcommand {
+        attach_console();
+        match Store::open(settings.database_path()).and_then(|store| cli::run(subcommand, &store)) {
+            Ok(output) => print!("{}", output),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     // Run the app.
-    SinceWhen::run(Settings::default())
+    SinceWhen::run(Settings::with_flags(settings))
 }
+
+/// Print to the console the app was started from.
+///
+/// The app is built without a console of its own on Windows, so command output would
+/// otherwise go nowhere.
+#[cfg(windows)]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // Fails without a console to attach to, like when started from the file explorer.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Print to the console the app was started from, which other platforms always do.
+#[cfg(not(windows))]
+fn attach_console() {}
@@ -0,0 +1,150 @@
+use log::info;
+use rusqlite::Connection;
+
+use crate::database::{Error, Result};
+
+/// Ordered schema migrations.
+///
+/// Migration `i` upgrades the data_base from schema version `i` to `i + 1`. The version is
+/// stored in SQLite's `user_version` pragma, which is `0` for files created by 1.0.4 and earlier.
+/// Append new migrations to the end; never edit one that has shipped.
+const MIGRATIONS: &[&str] = &[
+    // 1: The original events and occurrences tables.
+    "CREATE TABLE IF NOT EXISTS events (
+          id              INTEGER PRIMARY KEY,
+          name            TEXT NOT NULL UNIQUE
+          );
+     CREATE TABLE IF NOT EXISTS occurrences (
+          event_id        INTEGER,
+          year            INTEGER NOT NULL,
+          month           INTEGER NOT NULL,
+          day             INTEGER NOT NULL,
+          FOREIGN KEY(event_id) REFERENCES events(id)
+          );",
+    // 2: A single ISO date column, one occurrence per event per day, and cascading deletes.
+    // Orphaned occurrences and same day duplicates are dropped.
+    "CREATE TABLE occurrences_new (
+          id              INTEGER PRIMARY KEY,
+          event_id        INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+          date            TEXT NOT NULL
+          );
+     INSERT INTO occurrences_new (event_id, date)
+          SELECT DISTINCT event_id, printf('%04d-%02d-%02d', year, month, day)
+          FROM occurrences
+          WHERE event_id IN (SELECT id FROM events);
+     DROP TABLE occurrences;
+     ALTER TABLE occurrences_new RENAME TO occurrences;
+     CREATE UNIQUE INDEX occurrences_event_date ON occurrences(event_id, date);
+     CREATE INDEX occurrences_date ON occurrences(date);",
+    // 3: Deleted events go to the trash, stamped with when they were deleted.
+    "ALTER TABLE events ADD COLUMN deleted_at TEXT;
+     CREATE INDEX events_deleted_at ON events(deleted_at);",
+    // 4: Finished events can be archived, keeping their history out of the way.
+    "ALTER TABLE events ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;",
+    // 5: An optional note with each occurrence.
+    "ALTER TABLE occurrences ADD COLUMN note TEXT;",
+    // 6: Optional amount and price of each occurrence.
+    "ALTER TABLE occurrences ADD COLUMN quantity REAL;
+     ALTER TABLE occurrences ADD COLUMN unit TEXT;
+     ALTER TABLE occurrences ADD COLUMN cost REAL;",
+    // 7: Events measured by a meter, like an odometer, with the latest reading and the reading
+    // at each occurrence.
+    "ALTER TABLE events ADD COLUMN meter_unit TEXT;
+     ALTER TABLE events ADD COLUMN meter_reading REAL;
+     ALTER TABLE occurrences ADD COLUMN meter REAL;",
+    // 8: Tags, like "car" or "house", shared between events.
+    "CREATE TABLE tags (
+          id              INTEGER PRIMARY KEY,
+          name            TEXT NOT NULL UNIQUE
+          );
+     CREATE TABLE event_tags (
+          event_id        INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+          tag_id          INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+          PRIMARY KEY (event_id, tag_id)
+          );
+     CREATE INDEX event_tags_tag ON event_tags(tag_id);",
+    // 9: How often an event is meant to happen, in days.
+    "ALTER TABLE events ADD COLUMN target_interval_days INTEGER;",
+];
+
+/// The schema version this build of the app expects.
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Get the schema version of the data_base.
+///
+/// ### Arguments
+/// - conn: `&Connection` - The connection to the data_base.
+///
+/// ### Returns
+/// - `Result<u32>` - The schema version.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version;", [], |row| row.get(0))?)
+}
+
+/// Upgrade the data_base to the latest schema version.
+///
+/// Each pending migration runs in its own transaction together with the version bump, so a
+/// failed migration leaves the data_base at the last good version.
+///
+/// ### Arguments
+/// - conn: `&Connection` - The connection to the data_base.
+///
+/// ### Returns
+/// - `Result<u32>` - The schema version before migrating, or `Error::UnsupportedVersion`
+///   if the data_base was created by a newer version of the app.
+///
+/// ### Example
+/// ```
+/// # use rusqlite::Connection;
+/// # use since_when_lib::migrations::{migrate, schema_version, LATEST_VERSION};
+/// // A data_base created by since_when 1.0.4, including an orphaned occurrence.
+/// let conn = Connection::open_in_memory().unwrap();
+/// conn.execute_batch(
+///     "PRAGMA foreign_keys = OFF;
+///      CREATE TABLE events (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+///      CREATE TABLE occurrences (
+///          event_id INTEGER,
+///          year INTEGER NOT NULL,
+///          month INTEGER NOT NULL,
+///          day INTEGER NOT NULL,
+///          FOREIGN KEY(event_id) REFERENCES events(id));
+///      INSERT INTO events (name) VALUES ('Oil change');
+///      INSERT INTO occurrences VALUES (1, 2023, 4, 1), (1, 2023, 7, 2), (1, 2023, 7, 2), (0, 2023, 8, 3);",
+/// )
+/// .unwrap();
+/// assert_eq!(schema_version(&conn).unwrap(), 0);
+///
+/// assert_eq!(migrate(&conn).unwrap(), 0);
+/// assert_eq!(schema_version(&conn).unwrap(), LATEST_VERSION);
+/// // Existing history is kept, without duplicates or orphans.
+/// let dates: Vec<String> = conn
+///     .prepare("SELECT date FROM occurrences ORDER BY date;")
+///     .unwrap()
+///     .query_map([], |row| row.get(0))
+///     .unwrap()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(dates, ["2023-04-01", "2023-07-02"]);
+///
+/// // Migrating an up to date data_base does nothing.
+/// assert_eq!(migrate(&conn).unwrap(), LATEST_VERSION);
+///
+/// // A data_base from a newer app is left alone.
+/// conn.pragma_update(None, "user_version", LATEST_VERSION + 1).unwrap();
+/// assert!(migrate(&conn).is_err());
+/// ```
+pub fn migrate(conn: &Connection) -> Result<u32> {
+    let version = schema_version(conn)?;
+    if version > LATEST_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let next = i as u32 + 1;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", next)?;
+        tx.commit()?;
+        info!("Migrated data_base to schema version {}.", next);
+    }
+    Ok(version)
+}
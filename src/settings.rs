@@ -1,3 +1,22 @@
+use log::info;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// Command line flag for the data_base location.
+pub const DATABASE_FLAG: &str = "--database";
+/// Environment variable for the data_base location.
+pub const DATABASE_ENV: &str = "SINCE_WHEN_DB";
+/// Config file key for the data_base location.
+const DATABASE_KEY: &str = "database";
+/// Config file key for how many days deleted events stay in the trash.
+const TRASH_RETENTION_KEY: &str = "trash_retention_days";
+/// Days deleted events stay in the trash when the config file does not say.
+const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+/// Directory name used under the XDG base directories.
+const APP_DIR: &str = "since_when";
+/// The data_base file name, and where older versions kept it, in the working directory.
+const DATABASE_FILE: &str = "since_when.db";
+
 // App settings.
 pub struct Settings {
     text_size: u16,
@@ -6,6 +25,8 @@ pub struct Settings {
     calendar_text_size: u16,
     calendar_width: u16,
     padding: u16,
+    database_path: PathBuf,
+    trash_retention_days: u32,
 }
 
 impl Default for Settings {
@@ -23,8 +44,60 @@ impl Settings {
             calendar_text_size: 40,
             calendar_width: 75,
             padding: 5,
+            // Only `load` looks for the data_base, so drawing the app never touches the disk.
+            database_path: PathBuf::from(DATABASE_FILE),
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+        }
+    }
+
+    /// Load settings, resolving the data_base location.
+    ///
+    /// The first of these wins:
+    /// 1. `--database <path>` on the command line.
+    /// 2. The `SINCE_WHEN_DB` environment variable.
+    /// 3. `database = <path>` in `$XDG_CONFIG_HOME/since_when/since_when.conf`.
+    /// 4. `$XDG_DATA_HOME/since_when/since_when.db`. If there is none, but older versions
+    ///    left `since_when.db` in the working directory, it is copied there first.
+    ///
+    /// Deleted events are purged from the trash after `trash_retention_days = <days>`
+    /// in the config file, or 30 days.
+    ///
+    /// ### Arguments
+    /// - database: `Option<PathBuf>` - The `--database` path, as returned by `cli::parse`.
+    ///
+    /// ### Returns
+    /// - `Settings`
+    ///
+    /// ### Example
+    /// ```
+    /// # use std::path::Path;
+    /// # use since_when_lib::settings::Settings;
+    /// let settings = Settings::load(Some("/tmp/events.db".into()));
+    /// assert_eq!(settings.database_path(), Path::new("/tmp/events.db"));
+    /// ```
+    pub fn load(database: Option<PathBuf>) -> Self {
+        let config = config_file().and_then(|path| fs::read_to_string(path).ok());
+        let database_path = database
+            .or_else(|| env::var_os(DATABASE_ENV).map(PathBuf::from))
+            .or_else(|| {
+                config
+                    .as_deref()
+                    .and_then(|config| config_value(config, DATABASE_KEY))
+                    .map(PathBuf::from)
+            })
+            .unwrap_or_else(default_database_path);
+        let trash_retention_days = config
+            .as_deref()
+            .and_then(|config| config_value(config, TRASH_RETENTION_KEY))
+            .and_then(|days| days.parse().ok())
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+        Self {
+            database_path,
+            trash_retention_days,
+            ..Self::new()
         }
     }
+
     pub fn text_size(&self) -> u16 {
         self.text_size
     }
@@ -43,4 +116,89 @@ impl Settings {
     pub fn padding(&self) -> u16 {
         self.padding
     }
+    pub fn database_path(&self) -> &Path {
+        &self.database_path
+    }
+    pub fn trash_retention_days(&self) -> u32 {
+        self.trash_retention_days
+    }
+}
+
+/// Find a value in a config file of `key = value` lines.
+///
+/// Blank lines and lines starting with `#` are ignored.
+///
+/// ### Arguments
+/// - config: `&str` - The contents of the config file.
+/// - key: `&str` - The key to look up.
+///
+/// ### Returns
+/// - `Option<String>`
+fn config_value(config: &str, key: &str) -> Option<String> {
+    config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// Resolve an XDG base directory, falling back to a path under `$HOME`.
+///
+/// ### Arguments
+/// - var: `&str` - The XDG environment variable.
+/// - fallback: `&str` - The path relative to `$HOME` to use if `var` is unset.
+///
+/// ### Returns
+/// - `Option<PathBuf>` - `None` if neither `var` nor `$HOME` is usable.
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    // The spec says relative paths are invalid and should be ignored.
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+}
+
+/// The config file location.
+///
+/// ### Returns
+/// - `Option<PathBuf>` - `$XDG_CONFIG_HOME/since_when/since_when.conf`
+fn config_file() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join(APP_DIR).join("since_when.conf"))
+}
+
+/// The default data_base location.
+///
+/// Older versions kept the data_base in the working directory. If there is no data_base at
+/// the default location yet, that one is copied there, leaving the old file in place.
+///
+/// ### Returns
+/// - `PathBuf` - `$XDG_DATA_HOME/since_when/since_when.db`, or `since_when.db` in the
+///   working directory if no home directory can be found or it cannot be copied.
+fn default_database_path() -> PathBuf {
+    let legacy = PathBuf::from(DATABASE_FILE);
+    let Some(dir) = xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join(APP_DIR)) else {
+        return legacy;
+    };
+    let path = dir.join(DATABASE_FILE);
+    if path.exists() || !legacy.is_file() {
+        return path;
+    }
+    match fs::create_dir_all(&dir).and_then(|_| fs::copy(&legacy, &path)) {
+        Ok(_) => {
+            info!("Copied {} to {}", legacy.display(), path.display());
+            path
+        }
+        Err(e) => {
+            info!(
+                "Could not copy {} to {}: {}",
+                legacy.display(),
+                path.display(),
+                e
+            );
+            legacy
+        }
+    }
 }
@@ -0,0 +1,140 @@
+use iced::theme::Button::{Primary, Secondary};
+use iced::widget::{button, text, Button, Row};
+use iced::{Alignment, Command};
+use std::collections::BTreeSet;
+
+use crate::{
+    app::AppMessage,
+    database::{Result, Store},
+    settings::Settings,
+    utils::{background, new_button},
+};
+
+/// The tags picked to filter the Events page and the Calendar by.
+///
+/// Holds every tag in use, so drawing the filter never touches the data_base.
+#[derive(Debug, Clone, Default)]
+pub struct TagFilter {
+    tags: Vec<String>,
+    selected: BTreeSet<String>,
+}
+
+/// TagFilter implementation.
+impl<'a> TagFilter {
+    pub fn new() -> TagFilter {
+        Self::default()
+    }
+
+    /// Reload the tags in use from the data_base in the background.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::TagsLoaded` when done.
+    pub fn load(&self, store: &Store) -> Command<AppMessage> {
+        let store = store.clone();
+        background(move || store.tags(), AppMessage::TagsLoaded)
+    }
+
+    /// Show freshly loaded tags, dropping picked tags no event uses any more.
+    ///
+    /// ### Arguments
+    /// - tags: `Result<Vec<String>>` - The result of `load`.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from loading, if any.
+    pub fn loaded(&mut self, tags: Result<Vec<String>>) -> Result<()> {
+        self.tags = tags?;
+        let tags = &self.tags;
+        self.selected.retain(|tag| tags.contains(tag));
+        Ok(())
+    }
+
+    /// Pick or clear the tags to filter by.
+    ///
+    /// ### Arguments
+    /// - message: `AppMessage` - The message to process.
+    pub fn update(&mut self, message: AppMessage) {
+        match message {
+            AppMessage::ToggleTagFilter(tag) => {
+                if self.selected.contains(&tag) {
+                    self.selected.remove(&tag);
+                } else {
+                    self.selected.insert(tag);
+                }
+            }
+            AppMessage::ClearTagFilter => self.selected.clear(),
+            _ => (),
+        }
+    }
+
+    /// Whether an event with these tags passes the filter.
+    ///
+    /// ### Arguments
+    /// - tags: `&[String]` - The event's tags.
+    ///
+    /// ### Returns
+    /// - `bool` - `true` if no tags are picked, or the event has any of the picked tags.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::app::AppMessage;
+    /// # use since_when_lib::tags::TagFilter;
+    /// let mut filter = TagFilter::new();
+    /// let car = vec!["car".to_string()];
+    /// assert!(filter.matches(&car) && filter.matches(&[]));
+    ///
+    /// filter.update(AppMessage::ToggleTagFilter("house".to_string()));
+    /// assert!(!filter.matches(&car) && !filter.matches(&[]));
+    /// filter.update(AppMessage::ToggleTagFilter("car".to_string()));
+    /// assert!(filter.matches(&car));
+    ///
+    /// filter.update(AppMessage::ClearTagFilter);
+    /// assert!(filter.matches(&[]));
+    /// ```
+    pub fn matches(&self, tags: &[String]) -> bool {
+        self.selected.is_empty() || tags.iter().any(|tag| self.selected.contains(tag))
+    }
+
+    /// A tag chip that picks or drops the tag from the filter.
+    ///
+    /// ### Arguments
+    /// - tag: `&str` - The tag.
+    ///
+    /// ### Returns
+    /// - `Button<'a, AppMessage>` - The chip, highlighted if the tag is picked.
+    pub fn chip(&self, tag: &str) -> Button<'a, AppMessage> {
+        let settings = Settings::new();
+        button(text(tag).size(settings.text_size() - 4))
+            .on_press(AppMessage::ToggleTagFilter(tag.to_string()))
+            .padding([0, 6])
+            .style(if self.selected.contains(tag) {
+                Primary
+            } else {
+                Secondary
+            })
+    }
+
+    /// The row of tags to filter by.
+    ///
+    /// ### Returns
+    /// - `Option<Row<'a, AppMessage>>` - `None` if no event has a tag.
+    pub fn view(&self) -> Option<Row<'a, AppMessage>> {
+        if self.tags.is_empty() {
+            return None;
+        }
+        let settings = Settings::new();
+        let mut tag_row = Row::new()
+            .push(text("Tags:").size(settings.text_size()))
+            .spacing(settings.padding())
+            .align_items(Alignment::Center);
+        for tag in &self.tags {
+            tag_row = tag_row.push(self.chip(tag));
+        }
+        if !self.selected.is_empty() {
+            tag_row = tag_row.push(new_button(AppMessage::ClearTagFilter, text("All"), 80));
+        }
+        Some(tag_row)
+    }
+}
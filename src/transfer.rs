@@ -0,0 +1,740 @@
+use chrono::NaiveDate;
+use iced::alignment::Horizontal;
+use iced::widget::{checkbox, pick_list, row, text, text_input, Column, Text};
+use iced::{Alignment, Command};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, mem};
+
+use crate::{
+    app::AppMessage,
+    database::{Error, Result, Store},
+    events::{EventSummary, OccurrenceDetails},
+    ical,
+    settings::Settings,
+    utils::{background, new_button, parse_amount},
+};
+
+/// The first line of an exported CSV file, naming its columns.
+pub const CSV_HEADER: &str = "event,date,note,quantity,unit,cost,meter";
+
+/// An occurrence read from a file, to be added by `Store::import_occurrences`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRow {
+    /// The line of the file the occurrence was read from, for error messages.
+    pub line: usize,
+    pub event: String,
+    pub date: NaiveDate,
+    pub details: OccurrenceDetails,
+}
+
+/// A row of an imported file that could not be added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// What an import added, or would add in a dry run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportReport {
+    /// The events that did not exist yet, in the order they were created.
+    pub created: Vec<String>,
+    /// The number of occurrences added.
+    pub added: usize,
+    /// The number of occurrences skipped because their event already happened that day.
+    pub skipped: usize,
+    /// The rows that could not be added, by line.
+    pub errors: Vec<RowError>,
+    /// Whether nothing was saved.
+    pub dry_run: bool,
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |count: usize| if count != 1 { "s" } else { "" };
+        if self.dry_run {
+            write!(f, "Dry run, nothing was saved. Would add ")?;
+        } else {
+            write!(f, "Added ")?;
+        }
+        writeln!(
+            f,
+            "{} occurrence{} and {} new event{}, skipping {} already recorded.",
+            self.added,
+            plural(self.added),
+            self.created.len(),
+            plural(self.created.len()),
+            self.skipped
+        )?;
+        for error in &self.errors {
+            writeln!(f, "Line {}: {}", error.line, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write every occurrence as CSV, one row per occurrence.
+///
+/// Events in the trash are left out.
+///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+///
+/// ### Returns
+/// - `Result<String>` - The CSV, starting with `CSV_HEADER`.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::database::Store;
+/// # use since_when_lib::events::OccurrenceDetails;
+/// # use since_when_lib::transfer::export_csv;
+/// # use since_when_lib::utils::get_date;
+/// let store = Store::open_in_memory().unwrap();
+/// let details = OccurrenceDetails {
+///     note: Some("5W-30, synthetic".to_string()),
+///     cost: Some(45.5),
+///     ..Default::default()
+/// };
+/// store.add_event_with("Oil change", get_date(2023, 4, 1), &details).unwrap();
+/// assert_eq!(
+///     export_csv(&store).unwrap(),
+///     "event,date,note,quantity,unit,cost,meter\n\
+///     Oil change,2023-04-01,\"5W-30, synthetic\",,,45.5,\n"
+/// );
+/// ```
+pub fn export_csv(store: &Store) -> Result<String> {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    let number = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+    for (event, occurrence) in store.all_occurrences()? {
+        let details = occurrence.details;
+        let fields = [
+            event,
+            occurrence.date.to_string(),
+            details.note.unwrap_or_default(),
+            number(details.quantity),
+            details.unit.unwrap_or_default(),
+            number(details.cost),
+            number(details.meter),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv += &fields.join(",");
+        csv += "\n";
+    }
+    Ok(csv)
+}
+
+/// Read occurrences from CSV.
+///
+/// The first line names the columns. `event` and `date` are needed; `note`, `quantity`,
+/// `unit`, `cost` and `meter` are read if present, and any other column is ignored.
+///
+/// ### Arguments
+/// - csv: `&str` - The CSV, as written by `export_csv` or a spreadsheet.
+///
+/// ### Returns
+/// - `Result<(Vec<ImportRow>, Vec<RowError>)>` - The rows read and the rows that could not
+///   be read, or `Error::InvalidInput` if the file cannot be read as CSV at all.
+pub fn parse_csv(csv: &str) -> Result<(Vec<ImportRow>, Vec<RowError>)> {
+    let mut records = records(csv.trim_start_matches('\u{feff}'))?.into_iter();
+    let columns: HashMap<String, usize> = match records.next() {
+        Some((_, header)) => header
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.trim().to_lowercase(), i))
+            .collect(),
+        None => HashMap::new(),
+    };
+    if !columns.contains_key("event") || !columns.contains_key("date") {
+        return Err(Error::InvalidInput(format!(
+            "The first line must name the event and date columns, like \"{}\".",
+            CSV_HEADER
+        )));
+    }
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (line, fields) in records {
+        let field = |name: &str| {
+            columns
+                .get(name)
+                .and_then(|&i| fields.get(i))
+                .map_or("", |field| field.trim())
+        };
+        match read_row(line, field) {
+            Ok(row) => rows.push(row),
+            Err(e) => errors.push(RowError {
+                line,
+                message: e.to_string(),
+            }),
+        }
+    }
+    Ok((rows, errors))
+}
+
+/// Add the occurrences in CSV to the data_base, creating events that do not exist yet.
+///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+/// - csv: `&str` - The CSV, as read by `parse_csv`.
+/// - dry_run: `bool` - `true` to report what would change without saving anything.
+///
+/// ### Returns
+/// - `Result<ImportReport>` - What was, or would be, added, with the rows that could not be.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::database::Store;
+/// # use since_when_lib::transfer::{export_csv, import_csv};
+/// # use since_when_lib::utils::get_date;
+/// let store = Store::open_in_memory().unwrap();
+/// store.add_event("Oil change", get_date(2023, 4, 1)).unwrap();
+/// let csv = "Event,Date,Note\n\
+///     Oil change,2023-04-01,\n\
+///     Oil change,2023-07-02,\"Used \"\"synthetic\"\"\"\n\
+///     Haircut,April 3rd,\n\
+///     ,2023-04-03,\n\
+///     Haircut,2023-04-03,short\n";
+///
+/// let report = import_csv(&store, csv, false).unwrap();
+/// assert_eq!((report.added, report.skipped), (2, 1));
+/// assert_eq!(report.created, ["Haircut"]);
+/// let lines: Vec<usize> = report.errors.iter().map(|error| error.line).collect();
+/// assert_eq!(lines, [4, 5]);
+///
+/// // Exporting and importing again changes nothing.
+/// let csv = export_csv(&store).unwrap();
+/// let report = import_csv(&store, &csv, true).unwrap();
+/// assert_eq!((report.added, report.skipped), (0, 3));
+/// ```
+pub fn import_csv(store: &Store, csv: &str, dry_run: bool) -> Result<ImportReport> {
+    let (rows, errors) = parse_csv(csv)?;
+    let mut report = store.import_occurrences(&rows, dry_run)?;
+    report.errors.extend(errors);
+    report.errors.sort_by_key(|error| error.line);
+    Ok(report)
+}
+
+/// Write every occurrence to a CSV file.
+///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+/// - path: `&Path` - The file to write, replaced if it exists.
+///
+/// ### Returns
+/// - `Result<usize>` - The number of occurrences written, or `Error::InvalidInput` if the
+///   file cannot be written.
+pub fn export_file(store: &Store, path: &Path) -> Result<usize> {
+    let csv = export_csv(store)?;
+    fs::write(path, &csv)
+        .map_err(|e| Error::InvalidInput(format!("Could not write {}: {}", path.display(), e)))?;
+    // Count records rather than lines, as notes may hold line breaks.
+    Ok(records(&csv)?.len() - 1)
+}
+
+/// Describe a finished export.
+///
+/// ### Arguments
+/// - count: `usize` - How many things were written.
+/// - noun: `&str` - What was written, like "occurrence".
+/// - path: `&Path` - The file written.
+///
+/// ### Returns
+/// - `String` - Like "Exported 2 occurrences to events.csv."
+pub fn describe_export(count: usize, noun: &str, path: &Path) -> String {
+    format!(
+        "Exported {} {}{} to {}.",
+        count,
+        noun,
+        if count != 1 { "s" } else { "" },
+        path.display()
+    )
+}
+
+/// The calendar file that goes with the entered path, for exporting and loading calendars.
+///
+/// Calendar apps expect the `.ics` extension, and the entered path is usually the CSV file,
+/// so any other extension is replaced rather than written over or read as a calendar.
+///
+/// ### Arguments
+/// - path: `&Path` - The path entered on the page.
+///
+/// ### Returns
+/// - `PathBuf` - The path with the `.ics` extension.
+///
+/// ### Example
+/// ```
+/// # use std::path::Path;
+/// # use since_when_lib::transfer::calendar_path;
+/// // The CSV file offered by default is never written over.
+/// assert_eq!(calendar_path(Path::new("since_when.csv")), Path::new("since_when.ics"));
+/// assert_eq!(calendar_path(Path::new("events")), Path::new("events.ics"));
+/// assert_eq!(calendar_path(Path::new("Events.ICS")), Path::new("Events.ICS"));
+/// ```
+pub fn calendar_path(path: &Path) -> PathBuf {
+    match path.extension() {
+        Some(extension) if extension.eq_ignore_ascii_case("ics") => path.to_path_buf(),
+        _ => path.with_extension("ics"),
+    }
+}
+
+/// Add the occurrences in a CSV file to the data_base.
+///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+/// - path: `&Path` - The file to read.
+/// - dry_run: `bool` - `true` to report what would change without saving anything.
+///
+/// ### Returns
+/// - `Result<ImportReport>` - What was, or would be, added, or `Error::InvalidInput` if the
+///   file cannot be read.
+pub fn import_file(store: &Store, path: &Path, dry_run: bool) -> Result<ImportReport> {
+    let csv = fs::read_to_string(path)
+        .map_err(|e| Error::InvalidInput(format!("Could not read {}: {}", path.display(), e)))?;
+    import_csv(store, &csv, dry_run)
+}
+
+/// Read an occurrence from the fields of a row.
+///
+/// ### Arguments
+/// - line: `usize` - The line the row starts on.
+/// - field: `impl Fn(&str) -> &str` - The trimmed field in the named column, empty if missing.
+///
+/// ### Returns
+/// - `Result<ImportRow>` - `Error::InvalidInput` if the row has no event, or a date or number
+///   cannot be read.
+fn read_row<'a>(line: usize, field: impl Fn(&str) -> &'a str) -> Result<ImportRow> {
+    let event = field("event");
+    if event.is_empty() {
+        return Err(Error::InvalidInput("The row has no event.".to_string()));
+    }
+    let date = NaiveDate::parse_from_str(field("date"), "%Y-%m-%d").map_err(|_| {
+        Error::InvalidInput(format!("\"{}\" is not a YYYY-MM-DD date.", field("date")))
+    })?;
+    let text = |name| Some(field(name).to_string()).filter(|text| !text.is_empty());
+    Ok(ImportRow {
+        line,
+        event: event.to_string(),
+        date,
+        details: OccurrenceDetails {
+            note: text("note"),
+            quantity: parse_amount("quantity", field("quantity"))?,
+            unit: text("unit"),
+            cost: parse_amount("cost", field("cost"))?,
+            meter: parse_amount("meter reading", field("meter"))?,
+        },
+    })
+}
+
+/// Split CSV into records of fields.
+///
+/// Fields may be quoted to hold commas, line breaks and doubled `""` quotes. Blank lines
+/// are skipped.
+///
+/// ### Arguments
+/// - csv: `&str` - The CSV.
+///
+/// ### Returns
+/// - `Result<Vec<(usize, Vec<String>)>>` - The line each record starts on and its fields,
+///   or `Error::InvalidInput` if a quoted field is never closed.
+fn records(csv: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let (mut line, mut start) = (1, 1);
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => (),
+            '\n' if !quoted => {
+                fields.push(mem::take(&mut field));
+                records.push((start, mem::take(&mut fields)));
+                line += 1;
+                start = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(Error::InvalidInput(format!(
+            "Line {}: a quoted field is never closed.",
+            start
+        )));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+    records.retain(|(_, fields)| fields.iter().any(|field| !field.trim().is_empty()));
+    Ok(records)
+}
+
+/// Quote a CSV field if it needs it.
+///
+/// ### Arguments
+/// - field: `&str` - The field.
+///
+/// ### Returns
+/// - `String` - The field, quoted if it holds a comma, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Import/Export page state.
+#[derive(Debug, Clone)]
+pub struct TransferPage {
+    path: String,
+    dry_run: bool,
+    due_dates: bool,
+    /// The calendar loaded for importing, if any.
+    calendar: Option<ical::Calendar>,
+    /// `{summary: event}`, the event each calendar summary is imported as.
+    mapping: BTreeMap<String, String>,
+    from: String,
+    to: String,
+    /// What the last export or import did.
+    status: Option<String>,
+    working: bool,
+}
+
+/// Import/Export page implementation.
+impl<'a> TransferPage {
+    /// ### Arguments
+    /// - path: `PathBuf` - The file to offer for exporting and importing.
+    pub fn new(path: PathBuf) -> TransferPage {
+        Self {
+            path: path.display().to_string(),
+            dry_run: false,
+            due_dates: false,
+            calendar: None,
+            mapping: BTreeMap::new(),
+            from: String::new(),
+            to: String::new(),
+            status: None,
+            working: false,
+        }
+    }
+
+    /// Edit the file path and options, and export or import in the background.
+    ///
+    /// ### Arguments
+    /// - message: `AppMessage` - The message to process.
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::Exported`, `AppMessage::CalendarLoaded` or
+    ///   `AppMessage::Imported` when done.
+    pub fn update(&mut self, message: AppMessage, store: &Store) -> Command<AppMessage> {
+        let store = store.clone();
+        let path = PathBuf::from(self.path.trim());
+        match message {
+            AppMessage::TransferPath(path) => self.path = path,
+            AppMessage::DryRun(dry_run) => self.dry_run = dry_run,
+            AppMessage::DueDates(due_dates) => self.due_dates = due_dates,
+            AppMessage::ExportCsv if !self.working => {
+                self.working = true;
+                return background(
+                    move || {
+                        let count = export_file(&store, &path)?;
+                        Ok(describe_export(count, "occurrence", &path))
+                    },
+                    AppMessage::Exported,
+                );
+            }
+            AppMessage::ExportIcs if !self.working => {
+                self.working = true;
+                let due_dates = self.due_dates;
+                let path = calendar_path(&path);
+                return background(
+                    move || {
+                        let count = ical::export_ics_file(&store, &path, due_dates)?;
+                        Ok(describe_export(count, "calendar event", &path))
+                    },
+                    AppMessage::Exported,
+                );
+            }
+            AppMessage::MapSummary(summary, event) => {
+                self.mapping.insert(summary, event);
+            }
+            AppMessage::FromText(from) => self.from = from,
+            AppMessage::ToText(to) => self.to = to,
+            AppMessage::LoadCalendar if !self.working => {
+                self.working = true;
+                let path = calendar_path(&path);
+                return background(
+                    move || ical::Calendar::read(&path),
+                    AppMessage::CalendarLoaded,
+                );
+            }
+            AppMessage::ImportCalendar if !self.working => {
+                let Some(calendar) = self.calendar.clone() else {
+                    return Command::none();
+                };
+                let range = self.range();
+                let mapping: HashMap<String, String> = self.mapping.clone().into_iter().collect();
+                let dry_run = self.dry_run;
+                self.working = true;
+                return background(
+                    move || {
+                        let (from, to) = range?;
+                        calendar.import(&store, &mapping, from, to, dry_run)
+                    },
+                    AppMessage::Imported,
+                );
+            }
+            AppMessage::ImportCsv if !self.working => {
+                self.working = true;
+                let dry_run = self.dry_run;
+                return background(
+                    move || import_file(&store, &path, dry_run),
+                    AppMessage::Imported,
+                );
+            }
+            _ => (),
+        }
+        Command::none()
+    }
+
+    /// Show the result of an export.
+    ///
+    /// ### Arguments
+    /// - result: `Result<String>` - What was written where.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from exporting, if any.
+    pub fn exported(&mut self, result: Result<String>) -> Result<()> {
+        self.working = false;
+        self.status = Some(result?);
+        Ok(())
+    }
+
+    /// The date range entered for importing a calendar.
+    ///
+    /// ### Returns
+    /// - `Result<(Option<NaiveDate>, NaiveDate)>` - The first day, if any, and the last day,
+    ///   today if none was entered. `Error::InvalidInput` if a date cannot be read.
+    fn range(&self) -> Result<(Option<NaiveDate>, NaiveDate)> {
+        let date = |text: &str| {
+            let text = text.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| Error::InvalidInput(format!("\"{}\" is not a YYYY-MM-DD date.", text)))
+        };
+        let to = date(&self.to)?.unwrap_or_else(|| chrono::Local::now().date_naive());
+        Ok((date(&self.from)?, to))
+    }
+
+    /// Show a calendar loaded for importing, offering to import each summary under its own name.
+    ///
+    /// ### Arguments
+    /// - calendar: `Result<ical::Calendar>` - The calendar read.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from reading the calendar, if any.
+    pub fn calendar_loaded(&mut self, calendar: Result<ical::Calendar>) -> Result<()> {
+        self.working = false;
+        let calendar = calendar?;
+        self.mapping = calendar
+            .summaries()
+            .into_keys()
+            .map(|summary| (summary.clone(), summary))
+            .collect();
+        self.status = Some(format!(
+            "Loaded {} calendar event{}.",
+            calendar.events.len(),
+            if calendar.events.len() != 1 { "s" } else { "" }
+        ));
+        self.calendar = Some(calendar);
+        Ok(())
+    }
+
+    /// Show the result of an import.
+    ///
+    /// ### Arguments
+    /// - result: `Result<ImportReport>` - What was imported.
+    ///
+    /// ### Returns
+    /// - `Result<bool>` - Whether anything was saved, or the error from importing.
+    pub fn imported(&mut self, result: Result<ImportReport>) -> Result<bool> {
+        self.working = false;
+        let report = result?;
+        self.status = Some(report.to_string());
+        Ok(!report.dry_run && (report.added > 0 || !report.created.is_empty()))
+    }
+
+    /// View the event each calendar summary is imported as, and the dates to import.
+    ///
+    /// ### Arguments
+    /// - events: `&[EventSummary]` - The events to offer.
+    ///
+    /// ### Returns
+    /// - `Column<'a, AppMessage>`
+    fn mapping_view(&self, events: &[EventSummary]) -> Column<'a, AppMessage> {
+        let settings = Settings::new();
+        let names: Vec<String> = events.iter().map(|event| event.name.clone()).collect();
+        let mut mapping = Column::new()
+            .align_items(Alignment::Center)
+            .spacing(settings.padding())
+            .push(
+                text("Import calendar events as, leaving a name empty to skip them:")
+                    .size(settings.text_size()),
+            );
+        for (summary, event) in &self.mapping {
+            let picked = Some(event.clone()).filter(|event| names.contains(event));
+            let (input_summary, pick_summary) = (summary.clone(), summary.clone());
+            mapping = mapping.push(
+                row![
+                    text(summary.clone()).size(settings.text_size()).width(300),
+                    text_input("Skip", event)
+                        .on_input(move |event| AppMessage::MapSummary(input_summary.clone(), event))
+                        .size(settings.text_size())
+                        .width(300),
+                    pick_list(names.clone(), picked, move |event| {
+                        AppMessage::MapSummary(pick_summary.clone(), event)
+                    })
+                    .placeholder("Existing event")
+                    .text_size(settings.text_size())
+                    .width(250),
+                ]
+                .spacing(settings.spacing())
+                .align_items(Alignment::Center),
+            );
+        }
+        let range_row = row![
+            text_input("From YYYY-MM-DD", &self.from)
+                .on_input(AppMessage::FromText)
+                .size(settings.text_size())
+                .width(200),
+            text_input("To YYYY-MM-DD (today)", &self.to)
+                .on_input(AppMessage::ToText)
+                .size(settings.text_size())
+                .width(250),
+            new_button(
+                AppMessage::ImportCalendar,
+                text("Import Calendar"),
+                settings.add_button_size() + 50
+            ),
+        ]
+        .spacing(settings.spacing())
+        .align_items(Alignment::Center);
+        mapping.push(range_row)
+    }
+
+    /// View the import/export page.
+    ///
+    /// ### Arguments
+    /// - events: `&[EventSummary]` - The events calendar summaries can be imported as.
+    ///
+    /// ### Returns
+    /// - `Column<'a, AppMessage>` - The import/export page.
+    pub fn view(&self, events: &[EventSummary]) -> Column<'a, AppMessage> {
+        let settings = Settings::new();
+        let title = Text::new("Import / Export")
+            .size(settings.text_size() + 10)
+            .horizontal_alignment(Horizontal::Center);
+        let help = text(format!(
+            "CSV files have one row per occurrence, with the columns \"{}\".",
+            CSV_HEADER
+        ))
+        .size(settings.text_size());
+        let path_input = text_input("File", &self.path)
+            .on_input(AppMessage::TransferPath)
+            .size(settings.text_size())
+            .width(600);
+        let dry_run_checkbox = checkbox("Dry Run", self.dry_run)
+            .on_toggle(AppMessage::DryRun)
+            .size(settings.text_size())
+            .text_size(settings.text_size());
+        let action_row = row![
+            new_button(
+                AppMessage::ExportCsv,
+                text("Export"),
+                settings.add_button_size()
+            ),
+            new_button(
+                AppMessage::ImportCsv,
+                text("Import"),
+                settings.add_button_size()
+            ),
+            dry_run_checkbox,
+        ]
+        .spacing(settings.spacing())
+        .align_items(Alignment::Center);
+        // Calendars are exported and loaded next to the file, with the .ics extension.
+        let calendar_row = row![
+            new_button(
+                AppMessage::ExportIcs,
+                text("Export Calendar"),
+                settings.add_button_size() + 50
+            ),
+            checkbox("Include Due Dates", self.due_dates)
+                .on_toggle(AppMessage::DueDates)
+                .size(settings.text_size())
+                .text_size(settings.text_size()),
+            new_button(
+                AppMessage::LoadCalendar,
+                text("Load Calendar"),
+                settings.add_button_size() + 50
+            ),
+        ]
+        .spacing(settings.spacing())
+        .align_items(Alignment::Center);
+        let mut content = Column::new()
+            .align_items(Alignment::Center)
+            .spacing(settings.spacing())
+            .push(title)
+            .push(help)
+            .push(path_input)
+            .push(action_row)
+            .push(calendar_row);
+        if self.calendar.is_some() {
+            content = content.push(self.mapping_view(events));
+        }
+        if self.working {
+            content = content.push(text("Working...").size(settings.text_size()));
+        }
+        if let Some(status) = &self.status {
+            for line in status.lines() {
+                content = content.push(text(line).size(settings.text_size()));
+            }
+        }
+        // Navigation buttons.
+        let event_button = new_button(
+            AppMessage::EventsWindow,
+            text("Events"),
+            settings.add_button_size(),
+        );
+        let calendar_button = new_button(
+            AppMessage::CalendarWindow,
+            text("Calendar"),
+            settings.add_button_size(),
+        );
+        content.push(
+            row![calendar_button, event_button]
+                .align_items(Alignment::Center)
+                .spacing(settings.spacing()),
+        )
+    }
+}
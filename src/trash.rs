@@ -0,0 +1,216 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use iced::alignment::Horizontal;
+use iced::widget::{row, text, Column, Text};
+use iced::{Alignment, Command};
+
+use crate::{
+    app::AppMessage,
+    database::{Result, Store},
+    settings::Settings,
+    undo::Change,
+    utils::{background, new_button, record},
+};
+
+/// An event in the trash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    pub id: i64,
+    pub name: String,
+    /// When the event was moved to the trash, in UTC.
+    pub deleted_at: NaiveDateTime,
+    pub occurrences: usize,
+    /// The date of the latest occurrence, if any.
+    pub last: Option<NaiveDate>,
+}
+
+/// Trash page state.
+///
+/// Holds the trashed events, so drawing the page never touches the data_base.
+#[derive(Debug, Clone)]
+pub struct TrashPage {
+    entries: Vec<TrashEntry>,
+    retention_days: u32,
+    loading: bool,
+}
+
+/// Trash page implementation.
+impl<'a> TrashPage {
+    /// ### Arguments
+    /// - retention_days: `u32` - How many days deleted events are kept, for the page to mention.
+    pub fn new(retention_days: u32) -> TrashPage {
+        Self {
+            entries: Vec::new(),
+            retention_days,
+            loading: false,
+        }
+    }
+
+    /// Reload the trashed events from the data_base in the background.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::TrashLoaded` when done.
+    pub fn load(&mut self, store: &Store) -> Command<AppMessage> {
+        self.loading = true;
+        let store = store.clone();
+        background(move || store.trash(), AppMessage::TrashLoaded)
+    }
+
+    /// Show freshly loaded trashed events.
+    ///
+    /// ### Arguments
+    /// - entries: `Result<Vec<TrashEntry>>` - The result of `load`.
+    ///
+    /// ### Returns
+    /// - `Result<()>` - The error from loading, if any.
+    pub fn loaded(&mut self, entries: Result<Vec<TrashEntry>>) -> Result<()> {
+        self.loading = false;
+        self.entries = entries?;
+        Ok(())
+    }
+
+    /// Restore or purge trashed events.
+    ///
+    /// ### Arguments
+    /// - message: `AppMessage` - The message to process.
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Command<AppMessage>` - Sends `AppMessage::Changed` when done.
+    pub fn update(&mut self, message: AppMessage, store: &Store) -> Command<AppMessage> {
+        let store = store.clone();
+        match message {
+            AppMessage::RestoreEvent(id) => {
+                let Some(entry) = self.entries.iter().find(|entry| entry.id == id) else {
+                    return Command::none();
+                };
+                let name = entry.name.clone();
+                record(move || {
+                    store.restore_from_trash(id)?;
+                    Ok(Change::RestoredEvent(id, name))
+                })
+            }
+            AppMessage::PurgeEvent(id) => {
+                record(move || Ok(Change::DeletedEvent(store.purge_event(id)?)))
+            }
+            _ => Command::none(),
+        }
+    }
+
+    /// Create columns with header for the trash table.
+    ///
+    /// ### Arguments
+    /// - label: `&str` - The label for the column.
+    /// - width: `u16` - The width of the column.
+    ///
+    /// ### Returns
+    /// - `Column<AppMessage>` - The column with header.
+    fn make_column(label: &str, width: u16) -> Column<'a, AppMessage> {
+        let settings = Settings::new();
+        Column::new()
+            .spacing(settings.spacing())
+            .width(width)
+            .align_items(Alignment::Center)
+            .push(text(label).size(settings.text_size()))
+    }
+
+    /// View the trash page.
+    ///
+    /// ### Returns
+    /// - `Column<'a, AppMessage>` - The trash page.
+    pub fn view(&self) -> Column<'a, AppMessage> {
+        let settings = Settings::new();
+        let title = Text::new("Trash")
+            .size(settings.text_size() + 10)
+            .horizontal_alignment(Horizontal::Center);
+        let mut content = Column::new()
+            .align_items(Alignment::Center)
+            .spacing(settings.spacing())
+            .push(title);
+        if self.loading {
+            content = content.push(text("Loading...").size(settings.text_size()));
+        }
+        if self.entries.is_empty() {
+            content = content.push(text("The trash is empty.").size(settings.text_size()));
+        } else {
+            content = content.push(
+                text(format!(
+                    "Deleted events are purged after {} day{}.",
+                    self.retention_days,
+                    if self.retention_days != 1 { "s" } else { "" }
+                ))
+                .size(settings.text_size()),
+            );
+            // One row per trashed event, most recently deleted first.
+            let mut event_column = Self::make_column("Event", 250);
+            let mut deleted_column = Self::make_column("Deleted", 180);
+            let mut count_column = Self::make_column("Occurrences", 150);
+            let mut last_column = Self::make_column("Last", 180);
+            let mut action_column = Self::make_column("", 350);
+            for entry in &self.entries {
+                event_column = event_column.push(
+                    text(entry.name.clone())
+                        .size(settings.text_size())
+                        .height(40),
+                );
+                let deleted = Local
+                    .from_utc_datetime(&entry.deleted_at)
+                    .format("%b %e, %Y")
+                    .to_string();
+                deleted_column =
+                    deleted_column.push(text(deleted).size(settings.text_size()).height(40));
+                count_column = count_column.push(
+                    text(entry.occurrences)
+                        .size(settings.text_size())
+                        .height(40),
+                );
+                let last = match entry.last {
+                    Some(last) => last.format("%b %e, %Y").to_string(),
+                    None => "---".to_string(),
+                };
+                last_column = last_column.push(text(last).size(settings.text_size()).height(40));
+                action_column = action_column.push(
+                    row![
+                        new_button(AppMessage::RestoreEvent(entry.id), text("Restore"), 120),
+                        new_button(
+                            AppMessage::PurgeEvent(entry.id),
+                            text("Delete Forever"),
+                            180
+                        ),
+                    ]
+                    .spacing(settings.padding())
+                    .align_items(Alignment::Center),
+                );
+            }
+            content = content.push(
+                row![
+                    event_column,
+                    deleted_column,
+                    count_column,
+                    last_column,
+                    action_column
+                ]
+                .spacing(settings.spacing())
+                .align_items(Alignment::Start),
+            );
+        }
+        // Navigation buttons.
+        let event_button = new_button(
+            AppMessage::EventsWindow,
+            text("Events"),
+            settings.add_button_size(),
+        );
+        let calendar_button = new_button(
+            AppMessage::CalendarWindow,
+            text("Calendar"),
+            settings.add_button_size(),
+        );
+        content.push(
+            row![calendar_button, event_button]
+                .align_items(Alignment::Center)
+                .spacing(settings.spacing()),
+        )
+    }
+}
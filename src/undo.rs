@@ -0,0 +1,185 @@
+use crate::database::{Result, Store};
+use crate::events::{EventHistory, Merge, Occurrence};
+
+/// A change to the data_base that can be undone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// An event was added, or a purged event was put back.
+    AddedEvent(i64),
+    /// An event was purged along with its occurrences.
+    DeletedEvent(EventHistory),
+    /// The event with the given id and name was moved to the trash.
+    TrashedEvent(i64, String),
+    /// The event with the given id and name was taken out of the trash.
+    RestoredEvent(i64, String),
+    /// An occurrence was added, or a deleted occurrence was put back.
+    AddedOccurrence(i64),
+    /// An occurrence was deleted from the event with the given id.
+    DeletedOccurrence(i64, Occurrence),
+    /// Events were merged into another.
+    Merged(Merge),
+    /// A merge was undone. Holds the names of the merged events and of the target event.
+    Unmerged(Vec<String>, String),
+}
+
+impl Change {
+    /// Reverse the change in the data_base.
+    ///
+    /// ### Arguments
+    /// - store: `&Store` - The events data_base.
+    ///
+    /// ### Returns
+    /// - `Result<Change>` - The change that was made to reverse it. Reverting that puts this
+    ///   change back, so undo and redo are both a revert.
+    ///
+    /// ### Example
+    /// ```
+    /// # use since_when_lib::database::Store;
+    /// # use since_when_lib::undo::Change;
+    /// # use since_when_lib::utils::get_date;
+    /// let store = Store::open_in_memory().unwrap();
+    /// store.add_event("Haircut", get_date(2023, 4, 1)).unwrap();
+    /// let id = store.delete_event("Haircut").unwrap();
+    /// let change = Change::TrashedEvent(id, "Haircut".to_string());
+    ///
+    /// // Undo brings the event back, redo deletes it again.
+    /// let undone = change.revert(&store).unwrap();
+    /// assert!(store.get_event_id("Haircut").is_ok());
+    /// let redone = undone.revert(&store).unwrap();
+    /// assert!(store.get_event_id("Haircut").is_err());
+    /// assert_eq!(redone, change);
+    /// ```
+    pub fn revert(&self, store: &Store) -> Result<Change> {
+        match self {
+            Change::AddedEvent(id) => Ok(Change::DeletedEvent(store.purge_event(*id)?)),
+            Change::DeletedEvent(history) => {
+                store.restore_event(history)?;
+                Ok(Change::AddedEvent(history.id))
+            }
+            Change::TrashedEvent(id, name) => {
+                store.restore_from_trash(*id)?;
+                Ok(Change::RestoredEvent(*id, name.clone()))
+            }
+            Change::RestoredEvent(id, name) => {
+                store.trash_event(*id)?;
+                Ok(Change::TrashedEvent(*id, name.clone()))
+            }
+            Change::AddedOccurrence(id) => {
+                let (event_id, occurrence) = store.delete_occurrence(*id)?;
+                Ok(Change::DeletedOccurrence(event_id, occurrence))
+            }
+            Change::DeletedOccurrence(event_id, occurrence) => {
+                store.restore_occurrence(*event_id, occurrence)?;
+                Ok(Change::AddedOccurrence(occurrence.id))
+            }
+            Change::Merged(merge) => {
+                store.unmerge_events(merge)?;
+                let names = merge
+                    .sources
+                    .iter()
+                    .map(|source| source.name.clone())
+                    .collect();
+                Ok(Change::Unmerged(names, merge.target.name.clone()))
+            }
+            Change::Unmerged(sources, target) => {
+                let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+                Ok(Change::Merged(store.merge_events(&sources, target)?))
+            }
+        }
+    }
+
+    /// Describe a change that loses data, for offering to undo it.
+    ///
+    /// ### Returns
+    /// - `Option<String>` - `None` if nothing was lost.
+    pub fn describe_loss(&self) -> Option<String> {
+        match self {
+            Change::DeletedEvent(history) => {
+                Some(format!("Deleted \"{}\" for good.", history.name))
+            }
+            Change::TrashedEvent(_, name) => Some(format!("Moved \"{}\" to the trash.", name)),
+            Change::DeletedOccurrence(_, occurrence) => {
+                Some(format!("Deleted the occurrence on {}.", occurrence.date))
+            }
+            Change::Merged(merge) => Some(match merge.sources.as_slice() {
+                [source] => format!("Merged \"{}\" into \"{}\".", source.name, merge.target.name),
+                sources => format!(
+                    "Merged {} events into \"{}\".",
+                    sources.len(),
+                    merge.target.name
+                ),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Undo and redo stacks of changes.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    pub fn new() -> History {
+        Self::default()
+    }
+
+    /// Record a new change. Changes that were undone can no longer be redone.
+    ///
+    /// ### Arguments
+    /// - change: `Change` - The change that was made.
+    pub fn push(&mut self, change: Change) {
+        self.undo.push(change);
+        self.redo.clear();
+    }
+
+    /// Take the latest change to undo.
+    ///
+    /// ### Returns
+    /// - `Option<Change>`
+    pub fn pop_undo(&mut self) -> Option<Change> {
+        self.undo.pop()
+    }
+
+    /// Take the latest undone change to redo.
+    ///
+    /// ### Returns
+    /// - `Option<Change>`
+    pub fn pop_redo(&mut self) -> Option<Change> {
+        self.redo.pop()
+    }
+
+    /// Record the result of an undo, so it can be redone.
+    ///
+    /// ### Arguments
+    /// - change: `Change` - The change returned by `Change::revert`.
+    pub fn undone(&mut self, change: Change) {
+        self.redo.push(change);
+    }
+
+    /// Record the result of a redo, so it can be undone again.
+    ///
+    /// ### Arguments
+    /// - change: `Change` - The change returned by `Change::revert`.
+    pub fn redone(&mut self, change: Change) {
+        self.undo.push(change);
+    }
+
+    /// Put back a change that could not be undone, so undo can try it again.
+    ///
+    /// ### Arguments
+    /// - change: `Change` - The change taken by `pop_undo`.
+    pub fn retry_undo(&mut self, change: Change) {
+        self.undo.push(change);
+    }
+
+    /// Put back a change that could not be redone, so redo can try it again.
+    ///
+    /// ### Arguments
+    /// - change: `Change` - The change taken by `pop_redo`.
+    pub fn retry_redo(&mut self, change: Change) {
+        self.redo.push(change);
+    }
+}
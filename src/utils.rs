@@ -2,13 +2,16 @@ use chrono::NaiveDate;
 use iced::alignment::{Horizontal, Vertical};
 use iced::theme::Button::Secondary;
 use iced::widget::{button, text, Button, Row};
-use log::error;
-use std::collections::HashMap;
+use iced::Command;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use crate::app::AppMessage;
-use crate::database::{get_events, setup_connection};
-use crate::events::EventOccurrence;
+use crate::database::{Error, Result, Store};
+use crate::events::{
+    CostSummary, EventOccurrence, EventSummary, IntervalStats, Meter, MeterSummary, Prediction,
+};
 use crate::settings::Settings;
+use crate::undo::Change;
 
 /// Get the date from the day, month, and year.
 ///
@@ -40,8 +43,7 @@ pub fn get_days_since_now(events: &[EventOccurrence]) -> HashMap<String, Vec<i32
     let now = chrono::Local::now().naive_local().date();
     for event in events.iter() {
         // Calculate the days between the events and the current date.
-        let date = get_date(event.year, event.month, event.day);
-        let days = now.signed_duration_since(date).num_days() as i32;
+        let days = now.signed_duration_since(event.date).num_days() as i32;
         match days_since_now.get_mut(&event.name) {
             Some(days_vec) => {
                 days_vec.push(days);
@@ -82,7 +84,7 @@ pub fn get_days_since_now(events: &[EventOccurrence]) -> HashMap<String, Vec<i32
 /// assert_eq!(get_elapsed_days(&days_since), expected);
 /// ```
 pub fn get_elapsed_days(days_since: &HashMap<String, Vec<i32>>) -> HashMap<String, Vec<i32>> {
-    return days_since
+    days_since
         .iter()
         .map(|(name, days)| {
             (
@@ -90,110 +92,509 @@ pub fn get_elapsed_days(days_since: &HashMap<String, Vec<i32>>) -> HashMap<Strin
                 days.windows(2).map(|w| w[1] - w[0]).collect(),
             )
         })
-        .collect();
+        .collect()
 }
 
-/// Get the average elapsed days between occurrences for each event.
+/// Get statistics of the days between an event's occurrences.
 ///
 /// ### Arguments
-/// elapsed - `&HashMap<String, Vec<i32>>` - The elapsed days between occurrences for each event.
+/// - intervals: `&[i32]` - The elapsed days between occurrences, in any order.
 ///
 /// ### Returns
-/// `HashMap<String, i32>` - The average elapsed days between occurrences for each event.
+/// - `Option<IntervalStats>` - `None` if there are no intervals, as for an event that only
+///   happened once.
 ///
 /// ### Example
 /// ```
-/// # use std::collections::HashMap;
-/// # use since_when_lib::utils::get_averages;
-/// let mut averages = HashMap::new();
-/// let times_0 = vec![1, 11, 22, 33, 44];
-/// let times_1 = vec![1, 6, 11, 16, 21];
-/// averages.entry("foo".to_string()).or_insert(times_0);
-/// averages.entry("bar".to_string()).or_insert(times_1);
+/// # use since_when_lib::utils::get_interval_stats;
+/// let stats = get_interval_stats(&[2, 1]).unwrap();
+/// assert_eq!(stats.count, 2);
+/// // A day and a half is not rounded down.
+/// assert_eq!((stats.mean, stats.median), (1.5, 1.5));
+/// assert_eq!((stats.min, stats.max), (1, 2));
+/// assert_eq!(stats.std_dev, 0.5);
 ///
-/// let mut expected = HashMap::new();
-/// expected.entry("foo".to_string()).or_insert(22);
-/// expected.entry("bar".to_string()).or_insert(11);
+/// let stats = get_interval_stats(&[9, 2, 4, 4, 5, 4, 5, 7]).unwrap();
+/// assert_eq!((stats.mean, stats.median, stats.std_dev), (5.0, 4.5, 2.0));
+///
+/// // A single interval does not vary.
+/// let stats = get_interval_stats(&[30]).unwrap();
+/// assert_eq!((stats.count, stats.mean, stats.median, stats.std_dev), (1, 30.0, 30.0, 0.0));
 ///
-/// assert_eq!(get_averages(&averages), expected);
+/// // A single occurrence has no intervals.
+/// assert_eq!(get_interval_stats(&[]), None);
 /// ```
-pub fn get_averages(elapsed: &HashMap<String, Vec<i32>>) -> HashMap<String, i32> {
-    return elapsed
+pub fn get_interval_stats(intervals: &[i32]) -> Option<IntervalStats> {
+    let mut sorted = intervals.to_vec();
+    sorted.sort_unstable();
+    let (min, max) = (*sorted.first()?, *sorted.last()?);
+    let count = sorted.len();
+    let mean = sorted.iter().map(|days| *days as f64).sum::<f64>() / count as f64;
+    let middle = count / 2;
+    let median = if count % 2 == 1 {
+        sorted[middle] as f64
+    } else {
+        (sorted[middle - 1] + sorted[middle]) as f64 / 2.0
+    };
+    let variance = sorted
         .iter()
-        .map(|(name, days)| {
-            (
+        .map(|days| (*days as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count as f64;
+    Some(IntervalStats {
+        count,
+        mean,
+        median,
+        min,
+        max,
+        std_dev: variance.sqrt(),
+    })
+}
+
+/// Get the median of some numbers, rounding down between the middle two.
+///
+/// ### Arguments
+/// - values: `&[i32]` - The numbers, in any order.
+///
+/// ### Returns
+/// - `Option<i32>` - `None` if there are no numbers.
+fn median(values: &[i32]) -> Option<i32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[middle]),
+        _ => Some((sorted[middle - 1] + sorted[middle]).div_euclid(2)),
+    }
+}
+
+/// Predict when each event will happen next.
+///
+/// The next occurrence is expected a median interval after the latest one, give or take the
+/// median deviation from that interval.
+///
+/// ### Arguments
+/// - days_since - `&HashMap<String, Vec<i32>>` - The days since today for each occurrence for each event.
+/// - elapsed - `&HashMap<String, Vec<i32>>` - The elapsed days between occurrences for each event.
+/// - today - `NaiveDate` - The date the days are counted from.
+///
+/// ### Returns
+/// - `HashMap<String, Prediction>` - The prediction for each event that happened more than once.
+///
+/// ### Example
+/// ```
+/// # use std::collections::HashMap;
+/// # use since_when_lib::utils::{get_date, get_elapsed_days, get_predictions};
+/// let mut days_since = HashMap::new();
+/// days_since.insert("Haircut".to_string(), vec![5, 35, 63, 95, 140]);
+/// days_since.insert("Dentist".to_string(), vec![10]);
+/// let elapsed = get_elapsed_days(&days_since);
+///
+/// let predictions = get_predictions(&days_since, &elapsed, get_date(2023, 6, 10));
+/// let haircut = predictions["Haircut"];
+/// // Intervals of 30, 28, 32 and 45 days; the late one barely moves the prediction.
+/// assert_eq!(haircut.interval, 31);
+/// assert_eq!(haircut.date, get_date(2023, 7, 6));
+/// assert_eq!((haircut.earliest, haircut.latest), (get_date(2023, 7, 4), get_date(2023, 7, 8)));
+/// // One occurrence says nothing about the next.
+/// assert!(!predictions.contains_key("Dentist"));
+/// ```
+pub fn get_predictions(
+    days_since: &HashMap<String, Vec<i32>>,
+    elapsed: &HashMap<String, Vec<i32>>,
+    today: NaiveDate,
+) -> HashMap<String, Prediction> {
+    elapsed
+        .iter()
+        .filter_map(|(name, intervals)| {
+            let interval = median(intervals)?;
+            let deviations: Vec<i32> = intervals
+                .iter()
+                .map(|days| (days - interval).abs())
+                .collect();
+            let spread = median(&deviations)?;
+            let last = today - chrono::Duration::days(*days_since.get(name)?.first()? as i64);
+            let date = last + chrono::Duration::days(interval as i64);
+            Some((
                 name.to_owned(),
-                if !days.is_empty() {
-                    days.iter().sum::<i32>() / days.len() as i32
-                } else {
-                    0
+                Prediction {
+                    date,
+                    earliest: date - chrono::Duration::days(spread as i64),
+                    latest: date + chrono::Duration::days(spread as i64),
+                    interval,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Project the occurrences of an event expected between two dates.
+///
+/// ### Arguments
+/// - prediction: `&Prediction` - When the event will likely happen next.
+/// - first: `NaiveDate` - The first date to project onto.
+/// - last: `NaiveDate` - The last date to project onto.
+///
+/// ### Returns
+/// - `Vec<NaiveDate>` - The predicted date and every median interval after it, from `first`
+///   to `last`.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::events::Prediction;
+/// # use since_when_lib::utils::{get_date, project_occurrences};
+/// let prediction = Prediction {
+///     date: get_date(2023, 6, 5),
+///     earliest: get_date(2023, 6, 3),
+///     latest: get_date(2023, 6, 7),
+///     interval: 14,
+/// };
+/// let projected = project_occurrences(&prediction, get_date(2023, 6, 10), get_date(2023, 7, 31));
+/// assert_eq!(
+///     projected,
+///     [get_date(2023, 6, 19), get_date(2023, 7, 3), get_date(2023, 7, 17), get_date(2023, 7, 31)]
+/// );
+/// ```
+pub fn project_occurrences(
+    prediction: &Prediction,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Vec<NaiveDate> {
+    let interval = prediction.interval.max(1) as i64;
+    // Skip straight to the first projected date on or after `first`.
+    let behind = (first - prediction.date).num_days().max(0);
+    let mut date =
+        prediction.date + chrono::Duration::days((behind + interval - 1) / interval * interval);
+    let mut dates = Vec::new();
+    while date <= last {
+        dates.push(date);
+        date += chrono::Duration::days(interval);
+    }
+    dates
+}
+
+/// Get what each event has cost.
+///
+/// ### Arguments
+/// - events - `&[EventOccurrence]` - The events to process.
+/// - today - `NaiveDate` - The date to count days since the first occurrence to.
+///
+/// ### Returns
+/// - `HashMap<String, CostSummary>` - The costs of each event with at least one cost.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::events::EventOccurrence;
+/// # use since_when_lib::utils::{get_costs, get_date};
+/// let haircut = |day, cost| EventOccurrence {
+///     name: "Haircut".to_string(),
+///     date: get_date(2023, 4, day),
+///     archived: false,
+///     cost,
+///     meter: None,
+/// };
+/// let events = [haircut(1, Some(20.0)), haircut(11, None), haircut(21, Some(30.0))];
+///
+/// let costs = get_costs(&events, get_date(2023, 4, 26));
+/// assert_eq!(costs["Haircut"].total, 50.0);
+/// // Only occurrences with a cost count towards the average.
+/// assert_eq!(costs["Haircut"].average, 25.0);
+/// assert_eq!(costs["Haircut"].per_day, 2.0);
+/// ```
+pub fn get_costs(events: &[EventOccurrence], today: NaiveDate) -> HashMap<String, CostSummary> {
+    // Total, number of costs, and first date of each event.
+    let mut totals: HashMap<&str, (f64, u32, NaiveDate)> = HashMap::new();
+    for event in events.iter() {
+        let entry = totals
+            .entry(event.name.as_str())
+            .or_insert((0.0, 0, event.date));
+        if let Some(cost) = event.cost {
+            entry.0 += cost;
+            entry.1 += 1;
+        }
+        entry.2 = entry.2.min(event.date);
+    }
+    totals
+        .into_iter()
+        .filter(|(_, (_, count, _))| *count > 0)
+        .map(|(name, (total, count, first))| {
+            // Spending on the first day counts as one day.
+            let days = today.signed_duration_since(first).num_days().max(1);
+            (
+                name.to_string(),
+                CostSummary {
+                    total,
+                    average: total / count as f64,
+                    per_day: total / days as f64,
                 },
             )
         })
-        .collect();
+        .collect()
 }
 
-/// Sort events by days since now.
+/// Get how far an event's meter has run.
 ///
 /// ### Arguments
-/// - events - `&HashMap<String, Vec<i32>>` - The days since now for each event.
-/// - averages - `&HashMap<String, i32>` - The average elapsed days between occurrences for each event.
+/// - meter - `&Meter` - The event's meter, with the latest reading entered.
+/// - readings - `&[f64]` - The readings recorded with the event's occurrences, oldest first.
 ///
 /// ### Returns
-/// - `(String, i32, i32)` - (event_name, days_since, average)
+/// - `MeterSummary` - The distance from the reading at the latest occurrence to the latest
+///   reading, and the average distance between occurrences.
 ///
 /// ### Example
 /// ```
-/// # use std::collections::HashMap;
-/// # use since_when_lib::utils::sort_events;
-/// let mut events = HashMap::new();
-/// let times_0 = vec![4, 11, 22, 33, 44];
-/// let times_1 = vec![1, 6, 11, 16, 21];
-/// events.entry("foo".to_string()).or_insert(times_0);
-/// events.entry("bar".to_string()).or_insert(times_1);
-///
-/// let mut averages = HashMap::new();
-/// averages.entry("foo".to_string()).or_insert(22);
-/// averages.entry("bar".to_string()).or_insert(11);
-///
-/// let mut expected = Vec::new();
-/// expected.push(("bar".to_string(), 1, 11));
-/// expected.push(("foo".to_string(), 4, 22));
-///
-/// assert_eq!(sort_events(&events, &averages), expected);
-/// ```
-pub fn sort_events(
-    events: &HashMap<String, Vec<i32>>,
-    averages: &HashMap<String, i32>,
-) -> Vec<(String, i32, i32)> {
-    let mut sorted_events: Vec<(String, i32, i32)> = events
+/// # use since_when_lib::events::{Meter, MeterUnit};
+/// # use since_when_lib::utils::get_meter_summary;
+/// let odometer = Meter {
+///     unit: MeterUnit::Miles,
+///     reading: Some(38_200.0),
+/// };
+/// let summary = get_meter_summary(&odometer, &[30_000.0, 35_000.0, 37_000.0]);
+/// assert_eq!(summary.since_last, Some(1_200.0));
+/// assert_eq!(summary.average, Some(3_500.0));
+///
+/// // Without readings there is nothing to measure from.
+/// assert_eq!(get_meter_summary(&odometer, &[]).since_last, None);
+/// ```
+pub fn get_meter_summary(meter: &Meter, readings: &[f64]) -> MeterSummary {
+    let last = readings.last().copied();
+    // A reading recorded with an occurrence may be newer than the one entered for the event.
+    let latest = readings
         .iter()
-        .map(|(name, days)| (name.to_owned(), days[0], *averages.get(name).unwrap_or(&0)))
+        .copied()
+        .chain(meter.reading)
+        .reduce(f64::max);
+    let intervals: Vec<f64> = readings.windows(2).map(|w| w[1] - w[0]).collect();
+    MeterSummary {
+        unit: meter.unit,
+        since_last: last.zip(latest).map(|(last, latest)| latest - last),
+        average: (!intervals.is_empty())
+            .then(|| intervals.iter().sum::<f64>() / intervals.len() as f64),
+    }
+}
+
+/// Format a number for display, to at most one decimal place.
+///
+/// ### Arguments
+/// - number: `f64`
+///
+/// ### Returns
+/// - `String`
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::utils::format_number;
+/// assert_eq!(format_number(1_200.0), "1200");
+/// assert_eq!(format_number(0.1 + 0.2), "0.3");
+/// ```
+pub fn format_number(number: f64) -> String {
+    let rounded = format!("{:.1}", number);
+    match rounded.strip_suffix(".0") {
+        Some(whole) => whole.to_string(),
+        None => rounded,
+    }
+}
+
+/// Parse an optional number entered by the user.
+///
+/// ### Arguments
+/// - label: `&str` - What the number is, for the error message.
+/// - input: `&str` - The text entered.
+///
+/// ### Returns
+/// - `Result<Option<f64>>` - `None` if nothing was entered, or `Error::InvalidInput` if the
+///   text is not a number.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::utils::parse_amount;
+/// assert_eq!(parse_amount("cost", " 12.50 "), Ok(Some(12.5)));
+/// assert_eq!(parse_amount("cost", ""), Ok(None));
+/// assert!(parse_amount("cost", "twelve").is_err());
+/// ```
+pub fn parse_amount(label: &str, input: &str) -> Result<Option<f64>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    match input.parse::<f64>() {
+        Ok(amount) if amount.is_finite() => Ok(Some(amount)),
+        _ => Err(Error::InvalidInput(format!(
+            "\"{}\" is not a number for the {}.",
+            input, label
+        ))),
+    }
+}
+
+/// Parse a comma separated list of tags entered by the user.
+///
+/// Tags are trimmed and lowercased; empty and repeated tags are dropped.
+///
+/// ### Arguments
+/// - input: `&str` - The text entered.
+///
+/// ### Returns
+/// - `Vec<String>` - The tags, sorted.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::utils::parse_tags;
+/// assert_eq!(parse_tags(" Car, maintenance,,car "), ["car", "maintenance"]);
+/// assert!(parse_tags("").is_empty());
+/// ```
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let tags: BTreeSet<String> = input
+        .split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
         .collect();
-    sorted_events.sort_by(|a, b| a.1.cmp(&b.1));
-    sorted_events
+    tags.into_iter().collect()
 }
 
 /// Get the event details sorted by days since.
 ///
+/// ### Arguments
+/// - store: `&Store` - The events data_base.
+///
 /// ### Returns
-/// - `Vec<(String, i32, i32)>` - A vector of tuples containing the event name, days since, and average elapsed days.
-pub fn event_details() -> Vec<(String, i32, i32)> {
-    // Open the data_base.
-    let conn = setup_connection();
+/// - `Result<Vec<EventSummary>>` - The events with their days since and interval statistics.
+pub fn event_details(store: &Store) -> Result<Vec<EventSummary>> {
     // Get the events.
-    let events = get_events(&conn).unwrap_or_else(|e| {
-        error!("Error: {}", e);
-        vec![]
-    });
+    let events = store.get_events()?;
+    let today = chrono::Local::now().date_naive();
+    let archived: HashSet<&str> = events
+        .iter()
+        .filter(|event| event.archived)
+        .map(|event| event.name.as_str())
+        .collect();
     // Calculate the days since each event.
     let days_since_now = get_days_since_now(&events);
     // Calculate the elapsed days between event occurrences.
     let elapsed = get_elapsed_days(&days_since_now);
-    // Calculate the average elapsed days between occurrences.
-    let averages = get_averages(&elapsed);
+    // Predict when each event will happen next.
+    let predictions = get_predictions(&days_since_now, &elapsed, today);
+    // Calculate what each event has cost.
+    let costs = get_costs(&events, today);
+    // Collect the meter readings of each event, oldest first.
+    let meters = store.meters()?;
+    let mut readings: HashMap<&str, Vec<f64>> = HashMap::new();
+    for event in events.iter().rev() {
+        if let Some(meter) = event.meter {
+            readings.entry(event.name.as_str()).or_default().push(meter);
+        }
+    }
+    let mut tags = store.event_tags()?;
+    let targets = store.target_intervals()?;
+    let ids = store.event_ids()?;
+    let mut details: Vec<EventSummary> = days_since_now
+        .iter()
+        .map(|(name, days)| (name.to_owned(), days[0]))
+        .map(|(name, days_since)| EventSummary {
+            id: ids.get(&name).copied().unwrap_or_default(),
+            archived: archived.contains(name.as_str()),
+            cost: costs.get(&name).copied(),
+            meter: meters.get(&name).map(|meter| {
+                let readings = readings.get(name.as_str()).map(Vec::as_slice);
+                get_meter_summary(meter, readings.unwrap_or_default())
+            }),
+            tags: tags.remove(&name).unwrap_or_default(),
+            last: today - chrono::Duration::days(days_since as i64),
+            occurrences: days_since_now.get(&name).map_or(0, Vec::len),
+            target_interval_days: targets.get(&name).copied(),
+            prediction: predictions.get(&name).copied(),
+            intervals: elapsed
+                .get(&name)
+                .and_then(|intervals| get_interval_stats(intervals)),
+            name,
+            days_since,
+        })
+        .collect();
     // Sort the events by days since.
-    sort_events(&days_since_now, &averages)
+    details.sort_by_key(|event| event.days_since);
+    overdue_first(&mut details);
+    Ok(details)
+}
+
+/// Move overdue events to the top, most overdue first, keeping the order of the rest.
+///
+/// ### Arguments
+/// - events: `&mut [EventSummary]` - The events, sorted by days since.
+///
+/// ### Example
+/// ```
+/// # use since_when_lib::events::EventSummary;
+/// # use since_when_lib::utils::overdue_first;
+/// let event = |name: &str, days_since, target_interval_days| EventSummary {
+///     name: name.to_string(),
+///     days_since,
+///     target_interval_days,
+///     ..Default::default()
+/// };
+/// let mut events = vec![
+///     event("Haircut", 3, None),
+///     event("Water plants", 9, Some(7)),
+///     event("Oil change", 40, Some(90)),
+///     event("Replace furnace filter", 100, Some(90)),
+/// ];
+/// overdue_first(&mut events);
+/// let names: Vec<&str> = events.iter().map(|event| event.name.as_str()).collect();
+/// assert_eq!(names, ["Replace furnace filter", "Water plants", "Haircut", "Oil change"]);
+/// ```
+pub fn overdue_first(events: &mut [EventSummary]) {
+    events.sort_by_key(|event| match event.due_in() {
+        Some(due_in) if due_in < 0 => due_in,
+        _ => 0,
+    });
+}
+
+/// Run blocking work, like a data_base call, on a thread kept for blocking work, so it
+/// never stalls the threads that drive the app.
+///
+/// ### Arguments
+/// - work: `impl FnOnce() -> T` - The work to run.
+/// - message: `impl FnOnce(T) -> AppMessage` - Makes the message to send with the result.
+///
+/// ### Returns
+/// - `Command<AppMessage>` - Sends the message when done.
+pub fn background<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+    message: impl FnOnce(T) -> AppMessage + Send + 'static,
+) -> Command<AppMessage> {
+    Command::perform(
+        async move {
+            tokio::task::spawn_blocking(work)
+                .await
+                .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+        },
+        message,
+    )
+}
+
+/// Run a change to the data_base in the background, see `background`.
+///
+/// ### Arguments
+/// - change: `impl FnOnce() -> Result<()>` - The change to make.
+///
+/// ### Returns
+/// - `Command<AppMessage>` - Sends `AppMessage::Mutated` or `AppMessage::MutationFailed` when done.
+pub fn mutate(change: impl FnOnce() -> Result<()> + Send + 'static) -> Command<AppMessage> {
+    background(change, |result| match result {
+        Ok(()) => AppMessage::Mutated,
+        Err(e) => AppMessage::MutationFailed(e),
+    })
+}
+
+/// Run an undoable change to the data_base in the background, see `background`.
+///
+/// ### Arguments
+/// - change: `impl FnOnce() -> Result<Change>` - The change to make.
+///
+/// ### Returns
+/// - `Command<AppMessage>` - Sends `AppMessage::Changed` when done.
+pub fn record(change: impl FnOnce() -> Result<Change> + Send + 'static) -> Command<AppMessage> {
+    background(change, AppMessage::Changed)
 }
 
 /// Make a new button.